@@ -0,0 +1,146 @@
+//! Number of allocations made while parsing a large config
+//!
+//! Run with `cargo bench --bench allocations`. Uses the same config as the
+//! `parse` benchmark, but measures allocator calls per iteration instead of
+//! time, so changes in allocation count show up independently of noise.
+//! Tokenizing is not measured here as it does not allocate. Memory held by
+//! the parsed config is reported too.
+#[macro_use] extern crate criterion;
+extern crate nginx_config;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use criterion::Criterion;
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::Throughput;
+use nginx_config::parse_main;
+
+
+const SERVER: &str = include_str!("server.conf");
+const SERVERS: usize = 200;
+
+/// Counts every allocation ever made, including reallocations, and bytes
+/// currently alive
+struct Counting;
+
+static ALLOCS: AtomicU64 = AtomicU64::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static LIVE_ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        LIVE_ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        LIVE_ALLOCS.fetch_sub(1, Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize)
+        -> *mut u8
+    {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Criterion measurement reporting allocator calls instead of time
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = u64;
+    type Value = u64;
+    fn start(&self) -> u64 {
+        ALLOCS.load(Ordering::SeqCst)
+    }
+    fn end(&self, start: u64) -> u64 {
+        ALLOCS.load(Ordering::SeqCst) - start
+    }
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        a + b
+    }
+    fn zero(&self) -> u64 {
+        0
+    }
+    fn to_f64(&self, value: &u64) -> f64 {
+        *value as f64
+    }
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationsFormatter
+    }
+}
+
+struct AllocationsFormatter;
+
+impl ValueFormatter for AllocationsFormatter {
+    fn scale_values(&self, _typical: f64, _values: &mut [f64]) -> &'static str
+    {
+        "allocs"
+    }
+    fn scale_throughputs(&self, _typical: f64, throughput: &Throughput,
+        values: &mut [f64])
+        -> &'static str
+    {
+        if let Throughput::Bytes(bytes) = *throughput {
+            for val in values {
+                *val = *val * 1024.0 / bytes as f64;
+            }
+            "allocs/KiB"
+        } else {
+            "allocs"
+        }
+    }
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+fn config() -> String {
+    let mut text = String::from("http {\n");
+    for _ in 0..SERVERS {
+        text.push_str(SERVER);
+    }
+    text.push_str("}\n");
+    text
+}
+
+fn memory(text: &str) {
+    let bytes = LIVE_BYTES.load(Ordering::Relaxed);
+    let allocs = LIVE_ALLOCS.load(Ordering::Relaxed);
+    let ast = parse_main(text).unwrap();
+    println!("config: {} lines, {} bytes, {} bytes in {} allocations \
+        held after parsing",
+        text.lines().count(), text.len(),
+        LIVE_BYTES.load(Ordering::Relaxed) - bytes,
+        LIVE_ALLOCS.load(Ordering::Relaxed) - allocs);
+    drop(ast);
+}
+
+fn allocations(c: &mut Criterion<Allocations>) {
+    let text = config();
+    memory(&text);
+    let mut group = c.benchmark_group("allocations");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("parse", |b| b.iter(|| {
+        parse_main(&text).unwrap()
+    }));
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .with_measurement(Allocations)
+        .without_plots();
+    targets = allocations
+}
+criterion_main!(benches);
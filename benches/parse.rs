@@ -0,0 +1,39 @@
+//! Parsing throughput of a large config
+//!
+//! Run with `cargo bench`. The config is made of a realistic `server` block
+//! (`benches/server.conf`) repeated inside of `http`.
+#[macro_use] extern crate criterion;
+extern crate nginx_config;
+
+use criterion::{Criterion, Throughput};
+use nginx_config::parse_main;
+use nginx_config::tokenizer::tokens;
+
+
+const SERVER: &str = include_str!("server.conf");
+const SERVERS: usize = 200;
+
+fn config() -> String {
+    let mut text = String::from("http {\n");
+    for _ in 0..SERVERS {
+        text.push_str(SERVER);
+    }
+    text.push_str("}\n");
+    text
+}
+
+fn parsing(c: &mut Criterion) {
+    let text = config();
+    let mut group = c.benchmark_group("config");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("tokenize", |b| b.iter(|| {
+        tokens(&text).count()
+    }));
+    group.bench_function("parse", |b| b.iter(|| {
+        parse_main(&text).unwrap()
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, parsing);
+criterion_main!(benches);
@@ -25,6 +25,11 @@ fn parse_source<'a>(val: Token<'a>)
         let subnet = net.parse::<u8>()
             .map_err(|e| Error::unexpected_message(
                 format!("invalid subnet: {}", e)))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        if subnet > max {
+            return Err(Error::unexpected_message(
+                format!("invalid subnet: /{} is longer than {}", subnet, max)));
+        }
         return Ok(Source::Network(addr, subnet));
     } else {
         return Ok(Source::Ip(addr));
@@ -51,11 +56,25 @@ fn deny<'a>()
     .map(Item::Deny)
 }
 
+fn satisfy<'a>()
+    -> impl Parser<Output=Item, Input=TokenStream<'a>>
+{
+    use ast::Satisfy::*;
+    ident("satisfy")
+    .with(choice((
+        ident("all").map(|_| All),
+        ident("any").map(|_| Any),
+    )))
+    .skip(semi())
+    .map(Item::Satisfy)
+}
+
 pub fn directives<'a>()
     -> impl Parser<Output=Item, Input=TokenStream<'a>>
 {
     choice((
         allow(),
         deny(),
+        satisfy(),
     ))
 }
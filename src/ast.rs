@@ -1,10 +1,13 @@
 //! Abstract Syntax Tree types
 
 #![allow(missing_docs)] // structures are meant to be self-descriptive
+use std::collections::HashSet;
 use std::path::PathBuf;
 use std::net::{SocketAddr, IpAddr};
+use std::time::Duration;
 
 pub use value::{Value};
+use value;
 use position::Pos;
 use visitors::{DirectiveIter};
 
@@ -14,10 +17,20 @@ pub struct Main {
     pub directives: Vec<Directive>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 pub struct Directive {
     pub position: Pos,
     pub item: Item,
+    /// Comments just above the directive, text after `#` of each line
+    ///
+    /// Only filled in by a lenient parser, see
+    /// [`ParseOptions`](../struct.ParseOptions.html).
+    pub leading_comments: Vec<String>,
+    /// Comment right after the directive on the same line, text after `#`
+    ///
+    /// For blocks it's a comment after the closing `}`. Only filled in by
+    /// a lenient parser too.
+    pub trailing_comment: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -26,13 +39,20 @@ pub enum WorkerProcesses {
     Exact(u32),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 pub struct Http {
     pub position: (Pos, Pos),
     pub directives: Vec<Directive>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The `stream` block, for TCP and UDP proxying
+#[derive(Debug, Clone, Eq)]
+pub struct Stream {
+    pub position: (Pos, Pos),
+    pub directives: Vec<Directive>,
+}
+
+#[derive(Debug, Clone, Eq)]
 pub struct Server {
     pub position: (Pos, Pos),
     pub directives: Vec<Directive>,
@@ -41,21 +61,35 @@ pub struct Server {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IfCondition {
     NonEmpty(Value),
-    Eq(Value, String),
-    Neq(Value, String),
-    RegEq(Value, String, bool),
-    RegNeq(Value, String, bool),
-    Exists(Value),
-    NotExists(Value),
-    FileExists(Value),
-    FileNotExists(Value),
-    DirExists(Value),
-    DirNotExists(Value),
-    Executable(Value),
-    NotExecutable(Value),
+    /// Right side is a value, it may be quoted and contain variables
+    Eq(Value, Value),
+    Neq(Value, Value),
+    /// One of `~`, `~*`, `!~`, `!~*`
+    ///
+    /// Pattern is kept as a string, as `$` in a regex is not a variable
+    Regex {
+        variable: Value,
+        pattern: String,
+        negated: bool,
+        case_insensitive: bool,
+    },
+    /// One of `-f`, `-d`, `-e`, `-x` and their `!` negations
+    FileTest { negated: bool, kind: FileTestKind, operand: Value },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileTestKind {
+    /// `-f`, file exists
+    File,
+    /// `-d`, directory exists
+    Dir,
+    /// `-e`, file, directory or symlink exists
+    Exists,
+    /// `-x`, executable file exists
+    Executable,
+}
+
+#[derive(Debug, Clone, Eq)]
 pub struct If {
     pub position: (Pos, Pos),
     pub condition: IfCondition,
@@ -81,6 +115,8 @@ pub struct Listen {
     pub address: Address,
     pub default_server: bool,
     pub ssl: bool,
+    /// Only valid in `stream` context
+    pub udp: bool,
     pub ext: Option<HttpExt>,
     pub proxy_protocol: bool,
     pub setfib: Option<i32>,
@@ -94,8 +130,16 @@ pub struct Listen {
     pub bind: bool,
     pub ipv6only: Option<bool>,
     pub reuseport: bool,
+    pub multipath: bool,
     // TODO(tailhook) requires complex parser
     // so_keepalive: Option<KeepAlive>,
+    /// Options unknown to the parser, as written
+    ///
+    /// These are only accepted by a lenient parser, see
+    /// [`ParseOptions`](../struct.ParseOptions.html). They keep their order
+    /// relative to each other, but are printed after all the known options,
+    /// wherever they were written.
+    pub extra: Vec<String>,
 }
 
 impl Listen {
@@ -104,6 +148,7 @@ impl Listen {
             address,
             default_server: false,
             ssl: false,
+            udp: false,
             ext: None,
             proxy_protocol: false,
             setfib: None,
@@ -115,6 +160,8 @@ impl Listen {
             bind: false,
             ipv6only: None,
             reuseport: false,
+            multipath: false,
+            extra: Vec::new(),
         }
     }
 }
@@ -129,7 +176,7 @@ pub enum LocationPattern {
     Named(String),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 pub struct Location {
     pub position: (Pos, Pos),
     pub pattern: LocationPattern,
@@ -143,6 +190,27 @@ pub enum GzipStatic {
     Always,
 }
 
+/// Value of `open_file_cache`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenFileCache {
+    Off,
+    Cache {
+        /// Maximum number of elements in the cache
+        max: u64,
+        inactive: Option<Duration>,
+    },
+}
+
+/// Value of `server_tokens`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerTokens {
+    On,
+    Off,
+    Build,
+    /// Arbitrary signature, only supported by the commercial version
+    Custom(Value),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum GzipProxied {
     Off,
@@ -157,10 +225,20 @@ pub enum GzipProxied {
 }
 
 
+/// A name and a value, as in `proxy_set_header Host $host;`
+///
+/// Shared by directives setting headers or params: `add_header`,
+/// `add_trailer`, `proxy_set_header` and `fastcgi_param`.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct AddHeader {
-    pub field: Value,
+pub struct NameValue {
+    pub name: Value,
     pub value: Value,
+}
+
+/// Arguments of `add_header` and `add_trailer`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddHeader {
+    pub header: NameValue,
     pub always: bool,
 }
 
@@ -193,6 +271,45 @@ pub struct Map {
     pub patterns: Vec<(MapPattern, Value)>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BalancingMethod {
+    LeastConn,
+    IpHash,
+    Hash { key: Value, consistent: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpstreamServer {
+    pub address: Value,
+    pub weight: Option<u32>,
+    /// Other parameters, like `max_fails=3` or `backup`, as written
+    pub params: Vec<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upstream {
+    pub name: String,
+    /// Load balancing method, round-robin is used if none is specified
+    pub balancing: Option<BalancingMethod>,
+    pub servers: Vec<UpstreamServer>,
+    /// Number of idle connections to keep open per worker
+    pub keepalive: Option<u32>,
+    pub keepalive_requests: Option<u32>,
+    pub keepalive_timeout: Option<Duration>,
+    /// Comments and directives which have no fields above, like `zone`
+    ///
+    /// These are only kept by a lenient parser, and are printed after the
+    /// directives above.
+    pub directives: Vec<Directive>,
+}
+
+/// An entry of `types` block, e.g. `text/html html htm;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeEntry {
+    pub mime: String,
+    pub extensions: Vec<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ErrorPageResponse {
     /// The response code of a target uri
@@ -252,6 +369,16 @@ pub struct Expires {
     pub value: Value,
 }
 
+/// Value of `satisfy`
+#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+pub enum Satisfy {
+    /// Access is granted if all of access, auth_basic and other modules
+    /// allow it
+    All,
+    /// Access is granted if at least one of the modules allows it
+    Any,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Source {
     All,
@@ -260,17 +387,104 @@ pub enum Source {
     Network(IpAddr, u8),
 }
 
+/// An `allow` or `deny` rule, as returned by `Location::access_rules`
+/// and `LimitExcept::access_rules`
+#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+pub enum AccessRule<'a> {
+    Allow(&'a Source),
+    Deny(&'a Source),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyPass {
+    pub url: Value,
+}
+
+/// Where `proxy_pass` sends requests, as returned by `ProxyPass::target`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyTarget {
+    /// Host name, address or name of an upstream, with port if any
+    Host(String),
+    /// Path of a unix socket, like in `http://unix:/tmp/app.sock:/`
+    Unix(String),
+}
+
+/// Summary of a config, as returned by `Main::stats`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ConfigStats {
+    pub servers: usize,
+    pub locations: usize,
+    pub upstreams: usize,
+    /// All directives including blocks, but not comments
+    ///
+    /// Entries stored as fields of a block are not directives, so `server`
+    /// and `keepalive` in `upstream`, entries of `map` and `types` are not
+    /// counted, only the block itself is.
+    pub directives: usize,
+    /// Deepest nesting of blocks, e.g. 3 for `location` in `server` in
+    /// `http`, and 0 if there are no blocks
+    pub max_depth: usize,
+}
+
+/// A location with its server, as returned by `Main::locations`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocationRef<'a> {
+    /// Index of the enclosing `server` among all the servers in config,
+    /// counting from zero in order of appearance
+    pub server: usize,
+    pub location: &'a Location,
+}
+
+/// Addresses a server listens on, as returned by `Server::effective_listens`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EffectiveListens<'a> {
+    /// Own `listen` directives of the server
+    Explicit(Vec<&'a Listen>),
+    /// Server has no `listen` directives
+    ///
+    /// Nothing is inherited from `http` in this case, nginx listens on
+    /// `*:80` if it runs as root and on `*:8000` otherwise.
+    Default,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum ProxyHttpVersion {
     V1_0,
     V1_1,
 }
 
+/// A single cache of `ssl_session_cache`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SslSessionCacheKind {
+    /// `builtin` or `builtin:size`, size is in sessions
+    Builtin(Option<u64>),
+    /// `shared:name:size`, size is in bytes
+    Shared { name: String, size: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SslSessionCache {
+    Off,
+    None,
+    Caches(Vec<SslSessionCacheKind>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyRedirect {
+    Default,
+    Off,
+    Redirect { redirect: Value, replacement: Value },
+    /// Redirect matched by `~` or `~*` regex
+    ///
+    /// Pattern is kept as a string, as `$` in a regex is not a variable
+    Regex { pattern: String, case_insensitive: bool, replacement: Value },
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ProxyCacheValid {
-    Normal(Value),
-    Specific(Vec<u32>, Value),
-    Any(Value),
+    Normal(Duration),
+    Specific(Vec<u32>, Duration),
+    Any(Duration),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -306,6 +520,14 @@ pub struct AccessLogOptions {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFormat {
+    pub name: String,
+    pub escape: Option<String>,
+    /// All the string parts of the format concatenated
+    pub template: Value,
+}
+
+#[derive(Debug, Clone, Eq)]
 pub struct LimitExcept {
     pub position: (Pos, Pos),
     pub methods: Vec<String>,
@@ -331,6 +553,33 @@ pub enum ErrorLevel {
     Emerg,
 }
 
+/// A directive which isn't known to the parser
+///
+/// Such directives are only accepted by a lenient parser, see
+/// [`ParseOptions`](../struct.ParseOptions.html). Arguments are kept as is,
+/// without any validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generic {
+    pub name: String,
+    pub args: Vec<Value>,
+    pub block: Option<Vec<Directive>>,
+}
+
+/// A comment between directives
+///
+/// Comments are only kept by a lenient parser, see
+/// [`ParseOptions`](../struct.ParseOptions.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// Text after the `#` sign up to the end of line
+    pub text: String,
+    /// The text looks like a directive, i.e. it was probably commented out
+    ///
+    /// This is a cheap check of the text being a name followed by
+    /// arguments and `;`, `{` or `}`. The text isn't parsed.
+    pub looks_like_directive: bool,
+}
+
 /// The enum which represents nginx config directive
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Item {
@@ -338,14 +587,16 @@ pub enum Item {
     MasterProcess(bool),
     WorkerProcesses(WorkerProcesses),
     Http(Http),
+    Stream(Stream),
     Server(Server),
     Location(Location),
     Listen(Listen),
-    ProxyPass(Value),
-    ProxySetHeader { field: Value, value: Value },
+    ProxyPass(ProxyPass),
+    ProxySetHeader(NameValue),
     ProxyMethod(Value),
-    ProxyReadTimeout(Value),
-    ProxyConnectTimeout(Value),
+    ProxyReadTimeout(Duration),
+    ProxyConnectTimeout(Duration),
+    ProxySendTimeout(Duration),
     ProxyHideHeader(Value),
     ProxyPassHeader(Value),
     ProxyPassRequestBody(bool),
@@ -359,11 +610,17 @@ pub enum Item {
     ProxyCacheValid(ProxyCacheValid),
     ProxyNextUpstreamTries(Value),
     ProxyNextUpstreamTimeout(Value),
+    ProxyRedirect(ProxyRedirect),
     ProxyNextUpstream(Vec<ProxyNextUpstreamFlag>),
     Gzip(bool),
     GzipStatic(GzipStatic),
     GzipProxied(Vec<GzipProxied>),
+    GzipTypes(Vec<String>),
+    GzipCompLevel(u8),
+    /// Size in bytes
+    GzipMinLength(u64),
     AddHeader(AddHeader),
+    AddTrailer(AddHeader),
     Expires(Expires),
     Root(Value),
     Alias(Value),
@@ -382,13 +639,27 @@ pub enum Item {
     EmptyGif,
     Internal,
     LimitExcept(LimitExcept),
+    Types(Vec<TypeEntry>),
     Etag(bool),
+    MergeSlashes(bool),
+    AbsoluteRedirect(bool),
+    PortInRedirect(bool),
     RecursiveErrorPages(bool),
     ChunkedTransferEncoding(bool),
+    LogNotFound(bool),
+    RewriteLog(bool),
     KeepaliveTimeout(Value, Option<Value>),
-    ServerTokens(Value),
+    OpenFileCache(OpenFileCache),
+    ClientBodyTimeout(Duration),
+    ClientHeaderTimeout(Duration),
+    SendTimeout(Duration),
+    ServerTokens(ServerTokens),
     SslCertificate(Value),
     SslCertificateKey(Value),
+    SslDhparam(Value),
+    SslEcdhCurve(Value),
+    SslPreferServerCiphers(bool),
+    SslSessionCache(SslSessionCache),
     // openresty
     RewriteByLuaFile(Value),
     BalancerByLuaFile(Value),
@@ -404,18 +675,278 @@ pub enum Item {
     // access module
     Allow(Source),
     Deny(Source),
+    Satisfy(Satisfy),
     // log module
     AccessLog(AccessLog),
+    LogFormat(LogFormat),
     // real_ip module
     RealIpHeader(Value),
     RealIpRecursive(bool),
     SetRealIpFrom(RealIpFrom),
     // index module
     Index(Vec<Value>),
+    // charset module
+    Charset(Option<String>),
+    CharsetTypes(Vec<String>),
+    // auth_basic module
+    AuthBasic(Option<Value>),
+    AuthBasicUserFile(Value),
+    // geoip module
+    GeoipCountry(Value),
+    GeoipCity(Value),
+    GeoipOrg(Value),
+    // upstream module
+    Upstream(Upstream),
+    // fastcgi module
+    FastcgiPass(Value),
+    FastcgiParam { param: NameValue, if_not_empty: bool },
+    FastcgiIndex(Value),
+    // any directive unknown to the parser
+    Generic(Generic),
+    Comment(Comment),
+}
+
+/// Body of `Item::visit_values` and `Item::visit_values_mut`
+///
+/// Tokens after `f` are put after every `ref` and `&`, i.e. it's either
+/// `mut` or nothing.
+macro_rules! visit_values {
+    ($item: expr, $f: ident, $($m: tt)*) => {{
+        use self::Item::*;
+        match *$item {
+            Daemon(_) => {},
+            MasterProcess(_) => {},
+            WorkerProcesses(_) => {},
+            Http(_) => {},
+            Stream(_) => {},
+            Server(_) => {},
+            Location(_) => {},
+            LimitExcept(_) => {},
+            Listen(_) => {},
+            ProxyPass(ref $($m)* p) => $f(&$($m)* p.url),
+            ProxySetHeader(ref $($m)* h) => {
+                $f(&$($m)* h.name);
+                $f(&$($m)* h.value);
+            }
+            ProxyMethod(ref $($m)* v) => $f(v),
+            ProxyReadTimeout(..) => {},
+            ProxyConnectTimeout(..) => {},
+            ProxySendTimeout(..) => {},
+            ProxyHideHeader(ref $($m)* v) => $f(v),
+            ProxyPassHeader(ref $($m)* v) => $f(v),
+            ProxyCache(ref $($m)* v) => $f(v),
+            ProxyCacheKey(ref $($m)* v) => $f(v),
+            ProxyCacheValid(..) => {},
+            ProxyPassRequestHeaders(_) => {},
+            ProxyPassRequestBody(_) => {},
+            ProxyHttpVersion(..) => {},
+            ProxyIgnoreHeaders(..) => {},
+            ProxyInterceptErrors(..) => {},
+            ProxyBuffering(..) => {},
+            ProxyNextUpstreamTries(ref $($m)* v) => $f(v),
+            ProxyNextUpstreamTimeout(ref $($m)* v) => $f(v),
+            ProxyRedirect(::ast::ProxyRedirect::Redirect {
+                ref $($m)* redirect,
+                ref $($m)* replacement,
+            }) => {
+                $f(redirect);
+                $f(replacement);
+            }
+            ProxyRedirect(::ast::ProxyRedirect::Regex {
+                ref $($m)* replacement,
+                ..
+            }) => $f(replacement),
+            ProxyRedirect(..) => {}
+            ProxyNextUpstream(_) => {},
+            Gzip(_) => {},
+            GzipStatic(_) => {},
+            GzipProxied(_) => {},
+            GzipTypes(_) => {},
+            GzipCompLevel(_) => {},
+            GzipMinLength(_) => {},
+            AddHeader(ref $($m)* h) | AddTrailer(ref $($m)* h) => {
+                $f(&$($m)* h.header.name);
+                $f(&$($m)* h.header.value);
+            }
+            Expires(self::Expires { ref $($m)* value, .. }) => $f(value),
+            Root(ref $($m)* v) => $f(v),
+            Alias(ref $($m)* v) => $f(v),
+            ErrorPage(::ast::ErrorPage { ref $($m)* uri, .. }) => $f(uri),
+            DefaultType(ref $($m)* v) => $f(v),
+            ErrorLog { ref $($m)* file, .. } => $f(file),
+            Rewrite(ref $($m)* rw) => $f(&$($m)* rw.replacement),
+            Return(::ast::Return::Redirect { ref $($m)* url, .. }) => $f(url),
+            Return(::ast::Return::Text { text: Some(ref $($m)* t), .. })
+            => $f(t),
+            Return(::ast::Return::Text { text: None, .. }) => {},
+            If(self::If { ref $($m)* condition, .. }) => {
+                use self::IfCondition::*;
+                match condition {
+                    NonEmpty(ref $($m)* v) => $f(v),
+                    Eq(ref $($m)* l, ref $($m)* r) => {
+                        $f(l);
+                        $f(r);
+                    }
+                    Neq(ref $($m)* l, ref $($m)* r) => {
+                        $f(l);
+                        $f(r);
+                    }
+                    Regex { ref $($m)* variable, .. } => $f(variable),
+                    FileTest { ref $($m)* operand, .. } => $f(operand),
+                }
+            },
+            TryFiles(ref $($m)* tf) => {
+                for opt in &$($m)* tf.options {
+                    $f(opt);
+                }
+                match tf.last_option {
+                    TryFilesLastOption::Uri(ref $($m)* v) => $f(v),
+                    TryFilesLastOption::NamedLocation(..) => {},
+                    TryFilesLastOption::Code(..) => {},
+                }
+            },
+            Include(ref $($m)* v) => $f(v),
+            EmptyGif => {}
+            Internal => {}
+            Etag(..) => {}
+            MergeSlashes(..) => {}
+            AbsoluteRedirect(..) => {}
+            PortInRedirect(..) => {}
+            RecursiveErrorPages(..) => {}
+            ChunkedTransferEncoding(..) => {}
+            LogNotFound(..) => {}
+            Types(..) => {}
+            RewriteLog(..) => {}
+            OpenFileCache(..) => {}
+            ClientBodyTimeout(..) => {}
+            ClientHeaderTimeout(..) => {}
+            SendTimeout(..) => {}
+            KeepaliveTimeout(ref $($m)* a, ref $($m)* b) => {
+                $f(a);
+                if let Some(b) = b {
+                    $f(b);
+                }
+            }
+            ServerTokens(self::ServerTokens::Custom(ref $($m)* v)) => $f(v),
+            ServerTokens(_) => {}
+            SslCertificate(ref $($m)* v) => $f(v),
+            SslCertificateKey(ref $($m)* v) => $f(v),
+            SslDhparam(ref $($m)* v) => $f(v),
+            SslEcdhCurve(ref $($m)* v) => $f(v),
+            SslPreferServerCiphers(..) => {}
+            SslSessionCache(..) => {}
+            ServerName(_) => {},
+            Set { ref $($m)* value, .. } => $f(value),
+            Map(::ast::Map {
+                ref $($m)* expression,
+                ref $($m)* default,
+                ref $($m)* patterns,
+                ..
+            }) => {
+                $f(expression);
+                if let Some(ref $($m)* def) = default {
+                    $f(def);
+                }
+                for (_, v) in patterns {
+                    $f(v);
+                }
+            }
+            ClientMaxBodySize(ref $($m)* v) => $f(v),
+            // openresty
+            RewriteByLuaFile(ref $($m)* v) => $f(v),
+            BalancerByLuaFile(ref $($m)* v) => $f(v),
+            AccessByLuaFile(ref $($m)* v) => $f(v),
+            HeaderFilterByLuaFile(ref $($m)* v) => $f(v),
+            ContentByLuaFile(ref $($m)* v) => $f(v),
+            BodyFilterByLuaFile(ref $($m)* v) => $f(v),
+            LogByLuaFile(ref $($m)* v) => $f(v),
+            LuaNeedRequestBody(ref $($m)* v) => $f(v),
+            SslCertificateByLuaFile(ref $($m)* v) => $f(v),
+            SslSessionFetchByLuaFile(ref $($m)* v) => $f(v),
+            SslSessionStoreByLuaFile(ref $($m)* v) => $f(v),
+            // access
+            Allow(..) => {},
+            Deny(..) => {},
+            Satisfy(..) => {},
+            // log module
+            AccessLog(::ast::AccessLog::Off) => {},
+            AccessLog(::ast::AccessLog::On(ref $($m)* lg)) => {
+                $f(&$($m)* lg.path);
+                if let Some(ref $($m)* c) = lg.condition {
+                    $f(c);
+                }
+            },
+            LogFormat(ref $($m)* lf) => $f(&$($m)* lf.template),
+            // real_ip module
+            RealIpHeader(ref $($m)* v) => $f(v),
+            RealIpRecursive(..) => {},
+            SetRealIpFrom(..) => {},
+            // index module
+            Index(ref $($m)* items) => {
+                for v in items {
+                    $f(v);
+                }
+            }
+            // charset module
+            Charset(..) => {}
+            CharsetTypes(..) => {}
+            // auth_basic module
+            AuthBasic(Some(ref $($m)* v)) => $f(v),
+            AuthBasic(None) => {}
+            AuthBasicUserFile(ref $($m)* v) => $f(v),
+            GeoipCountry(ref $($m)* v) => $f(v),
+            GeoipCity(ref $($m)* v) => $f(v),
+            GeoipOrg(ref $($m)* v) => $f(v),
+            Upstream(::ast::Upstream {
+                ref $($m)* balancing,
+                ref $($m)* servers,
+                ..
+            }) => {
+                if let Some(BalancingMethod::Hash { ref $($m)* key, .. })
+                    = *balancing
+                {
+                    $f(key);
+                }
+                for srv in servers {
+                    $f(&$($m)* srv.address);
+                    for param in &$($m)* srv.params {
+                        $f(param);
+                    }
+                }
+            }
+            FastcgiPass(ref $($m)* v) => $f(v),
+            FastcgiParam { ref $($m)* param, .. } => {
+                $f(&$($m)* param.name);
+                $f(&$($m)* param.value);
+            }
+            FastcgiIndex(ref $($m)* v) => $f(v),
+            Generic(ref $($m)* g) => {
+                for v in &$($m)* g.args {
+                    $f(v);
+                }
+            }
+            Comment(..) => {}
+        }
+    }};
 }
 
 impl Item {
 
+    /// Returns the keyword the directive starts with, e.g. `proxy_pass`
+    ///
+    /// For generic directives it's the name as written in the config, and
+    /// for comments it's `#`.
+    pub fn keyword(&self) -> &str {
+        match *self {
+            Item::Generic(ref g) => &g.name,
+            _ => self.directive_name(),
+        }
+    }
+
+    /// Returns the name of the directive, e.g. `proxy_pass`
+    ///
+    /// Generic directives have no static name, so this returns an empty
+    /// string for them. Use [`keyword`](#method.keyword) to get their name.
     pub fn directive_name(&self) -> &'static str {
         use self::Item::*;
         match *self {
@@ -423,15 +954,17 @@ impl Item {
             MasterProcess(..) => "master_process",
             WorkerProcesses(..) => "worker_processes",
             Http(..) => "http",
+            Stream(..) => "stream",
             Server(..) => "server",
             Location(..) => "location",
             LimitExcept(..) => "limit_except",
             Listen(..) => "listen",
             ProxyPass(..) => "proxy_pass",
-            ProxySetHeader {..} => "proxy_set_header",
+            ProxySetHeader(..) => "proxy_set_header",
             ProxyMethod {..} => "proxy_method",
             ProxyReadTimeout {..} => "proxy_read_timeout",
             ProxyConnectTimeout {..} => "proxy_connect_timeout",
+            ProxySendTimeout {..} => "proxy_send_timeout",
             ProxyHideHeader {..} => "proxy_hide_header",
             ProxyPassHeader {..} => "proxy_pass_header",
             ProxyPassRequestHeaders(..) => "proxy_pass_request_headers",
@@ -445,11 +978,16 @@ impl Item {
             ProxyCacheValid(..) => "proxy_cache_valid",
             ProxyNextUpstreamTries(..) => "proxy_next_upstream_tries",
             ProxyNextUpstreamTimeout(..) => "proxy_next_upstream_timeout",
+            ProxyRedirect(..) => "proxy_redirect",
             ProxyNextUpstream(..) => "proxy_next_upstream",
             Gzip(..) => "gzip",
             GzipStatic(..) => "gzip_static",
             GzipProxied(..) => "gzip_proxied",
+            GzipTypes(..) => "gzip_types",
+            GzipCompLevel(..) => "gzip_comp_level",
+            GzipMinLength(..) => "gzip_min_length",
             AddHeader(..) => "add_header",
+            AddTrailer(..) => "add_trailer",
             Expires(..) => "expires",
             Root(..) => "root",
             Alias(..) => "alias",
@@ -468,12 +1006,26 @@ impl Item {
             EmptyGif => "empty_gif",
             Internal => "internal",
             Etag(..) => "etag",
+            MergeSlashes(..) => "merge_slashes",
+            AbsoluteRedirect(..) => "absolute_redirect",
+            PortInRedirect(..) => "port_in_redirect",
             RecursiveErrorPages(..) => "recursive_error_pages",
             ChunkedTransferEncoding(..) => "chunked_transfer_encoding",
+            LogNotFound(..) => "log_not_found",
+            Types(..) => "types",
+            RewriteLog(..) => "rewrite_log",
             KeepaliveTimeout(..) => "keepalive_timeout",
+            OpenFileCache(..) => "open_file_cache",
+            ClientBodyTimeout(..) => "client_body_timeout",
+            ClientHeaderTimeout(..) => "client_header_timeout",
+            SendTimeout(..) => "send_timeout",
             ServerTokens(..) => "server_tokens",
             SslCertificate(..) => "ssl_certificate",
             SslCertificateKey(..) => "ssl_certificate_key",
+            SslDhparam(..) => "ssl_dhparam",
+            SslEcdhCurve(..) => "ssl_ecdh_curve",
+            SslPreferServerCiphers(..) => "ssl_prefer_server_ciphers",
+            SslSessionCache(..) => "ssl_session_cache",
             // openresty
             RewriteByLuaFile(..) => "rewrite_by_lua_file",
             BalancerByLuaFile(..) => "balancer_by_lua_file",
@@ -489,14 +1041,32 @@ impl Item {
             // access module
             Allow(..) => "allow",
             Deny(..) => "deny",
+            Satisfy(..) => "satisfy",
             // log module
             AccessLog(..) => "access_log",
+            LogFormat(..) => "log_format",
             // real_ip module
             RealIpHeader(..) => "real_ip_header",
             RealIpRecursive(..) => "real_ip_recursive",
             SetRealIpFrom(..) => "set_real_ip_from",
             // index module
             Index(..) => "index",
+            // charset module
+            Charset(..) => "charset",
+            CharsetTypes(..) => "charset_types",
+            // auth_basic module
+            AuthBasic(..) => "auth_basic",
+            AuthBasicUserFile(..) => "auth_basic_user_file",
+            // geoip module
+            GeoipCountry(..) => "geoip_country",
+            GeoipCity(..) => "geoip_city",
+            GeoipOrg(..) => "geoip_org",
+            Upstream(..) => "upstream",
+            FastcgiPass(..) => "fastcgi_pass",
+            FastcgiParam {..} => "fastcgi_param",
+            FastcgiIndex(..) => "fastcgi_index",
+            Generic(..) => "",
+            Comment(..) => "#",
         }
     }
 
@@ -507,6 +1077,7 @@ impl Item {
             MasterProcess(_) => None,
             WorkerProcesses(_) => None,
             Http(ref h) => Some(&h.directives[..]),
+            Stream(ref s) => Some(&s.directives[..]),
             Server(ref s) => Some(&s.directives[..]),
             Location(ref l) => Some(&l.directives[..]),
             LimitExcept(ref l) => Some(&l.directives[..]),
@@ -514,10 +1085,11 @@ impl Item {
             ProxyPass(_) => None,
             ProxyPassRequestHeaders(..) => None,
             ProxyPassRequestBody(..) => None,
-            ProxySetHeader {..} => None,
+            ProxySetHeader(..) => None,
             ProxyMethod {..} => None,
             ProxyReadTimeout {..} => None,
             ProxyConnectTimeout {..} => None,
+            ProxySendTimeout {..} => None,
             ProxyHideHeader {..} => None,
             ProxyPassHeader {..} => None,
             ProxyHttpVersion {..} => None,
@@ -529,11 +1101,16 @@ impl Item {
             ProxyCacheValid(..) => None,
             ProxyNextUpstreamTries(..) => None,
             ProxyNextUpstreamTimeout(..) => None,
+            ProxyRedirect(..) => None,
             ProxyNextUpstream(..) => None,
             Gzip(..) => None,
             GzipStatic(..) => None,
             GzipProxied(..) => None,
+            GzipTypes(..) => None,
+            GzipCompLevel(..) => None,
+            GzipMinLength(..) => None,
             AddHeader(..) => None,
+            AddTrailer(..) => None,
             Expires(..) => None,
             Root(..) => None,
             Alias(..) => None,
@@ -552,12 +1129,26 @@ impl Item {
             EmptyGif => None,
             Internal => None,
             Etag(..) => None,
+            MergeSlashes(..) => None,
+            AbsoluteRedirect(..) => None,
+            PortInRedirect(..) => None,
             RecursiveErrorPages(..) => None,
             ChunkedTransferEncoding(..) => None,
+            LogNotFound(..) => None,
+            Types(..) => None,
+            RewriteLog(..) => None,
             KeepaliveTimeout(..) => None,
+            OpenFileCache(..) => None,
+            ClientBodyTimeout(..) => None,
+            ClientHeaderTimeout(..) => None,
+            SendTimeout(..) => None,
             ServerTokens(..) => None,
             SslCertificate(..) => None,
             SslCertificateKey(..) => None,
+            SslDhparam(..) => None,
+            SslEcdhCurve(..) => None,
+            SslPreferServerCiphers(..) => None,
+            SslSessionCache(..) => None,
             // openresty
             RewriteByLuaFile(..) => None,
             BalancerByLuaFile(..) => None,
@@ -573,14 +1164,31 @@ impl Item {
             // access
             Allow(..) => None,
             Deny(..) => None,
+            Satisfy(..) => None,
             // log module
             AccessLog(..) => None,
+            LogFormat(..) => None,
             // real_ip module
             RealIpHeader(..) => None,
             RealIpRecursive(..) => None,
             SetRealIpFrom(..) => None,
             // index module
             Index(..) => None,
+            // charset module
+            Charset(..) => None,
+            CharsetTypes(..) => None,
+            // auth_basic module
+            AuthBasic(..) => None,
+            AuthBasicUserFile(..) => None,
+            GeoipCountry(..) => None,
+            GeoipCity(..) => None,
+            GeoipOrg(..) => None,
+            Upstream(ref u) => Some(&u.directives[..]),
+            FastcgiPass(..) => None,
+            FastcgiParam {..} => None,
+            FastcgiIndex(..) => None,
+            Generic(ref g) => g.block.as_ref().map(|b| &b[..]),
+            Comment(..) => None,
         }
     }
 
@@ -591,15 +1199,17 @@ impl Item {
             MasterProcess(_) => None,
             WorkerProcesses(_) => None,
             Http(ref mut h) => Some(&mut h.directives),
+            Stream(ref mut s) => Some(&mut s.directives),
             Server(ref mut s) => Some(&mut s.directives),
             Location(ref mut l) => Some(&mut l.directives),
             LimitExcept(ref mut l) => Some(&mut l.directives),
             Listen(_) => None,
             ProxyPass(_) => None,
-            ProxySetHeader {..} => None,
+            ProxySetHeader(..) => None,
             ProxyMethod {..} => None,
             ProxyReadTimeout {..} => None,
             ProxyConnectTimeout {..} => None,
+            ProxySendTimeout {..} => None,
             ProxyHideHeader {..} => None,
             ProxyPassHeader {..} => None,
             ProxyPassRequestHeaders(_) => None,
@@ -613,11 +1223,16 @@ impl Item {
             ProxyCacheValid {..} => None,
             ProxyNextUpstreamTries(..) => None,
             ProxyNextUpstreamTimeout(..) => None,
+            ProxyRedirect(..) => None,
             ProxyNextUpstream(..) => None,
             Gzip(..) => None,
             GzipStatic(..) => None,
             GzipProxied(..) => None,
+            GzipTypes(..) => None,
+            GzipCompLevel(..) => None,
+            GzipMinLength(..) => None,
             AddHeader(..) => None,
+            AddTrailer(..) => None,
             Expires(..) => None,
             Root(..) => None,
             Alias(..) => None,
@@ -636,12 +1251,26 @@ impl Item {
             EmptyGif => None,
             Internal => None,
             Etag(..) => None,
+            MergeSlashes(..) => None,
+            AbsoluteRedirect(..) => None,
+            PortInRedirect(..) => None,
             RecursiveErrorPages(..) => None,
             ChunkedTransferEncoding(..) => None,
+            LogNotFound(..) => None,
+            Types(..) => None,
+            RewriteLog(..) => None,
             KeepaliveTimeout(..) => None,
+            OpenFileCache(..) => None,
+            ClientBodyTimeout(..) => None,
+            ClientHeaderTimeout(..) => None,
+            SendTimeout(..) => None,
             ServerTokens(..) => None,
             SslCertificate(..) => None,
             SslCertificateKey(..) => None,
+            SslDhparam(..) => None,
+            SslEcdhCurve(..) => None,
+            SslPreferServerCiphers(..) => None,
+            SslSessionCache(..) => None,
             // openresty
             RewriteByLuaFile(..) => None,
             BalancerByLuaFile(..) => None,
@@ -657,14 +1286,31 @@ impl Item {
             // access
             Allow(..) => None,
             Deny(..) => None,
+            Satisfy(..) => None,
             // log module
             AccessLog(..) => None,
+            LogFormat(..) => None,
             // real_ip module
             RealIpHeader(..) => None,
             RealIpRecursive(..) => None,
             SetRealIpFrom(..) => None,
             // index module
             Index(..) => None,
+            // charset module
+            Charset(..) => None,
+            CharsetTypes(..) => None,
+            // auth_basic module
+            AuthBasic(..) => None,
+            AuthBasicUserFile(..) => None,
+            GeoipCountry(..) => None,
+            GeoipCity(..) => None,
+            GeoipOrg(..) => None,
+            Upstream(ref mut u) => Some(&mut u.directives),
+            FastcgiPass(..) => None,
+            FastcgiParam {..} => None,
+            FastcgiIndex(..) => None,
+            Generic(ref mut g) => g.block.as_mut(),
+            Comment(..) => None,
         }
     }
 
@@ -679,153 +1325,45 @@ impl Item {
     pub(crate) fn visit_values_mut<F>(&mut self, mut f: F)
         where F: FnMut(&mut Value)
     {
-        use self::Item::*;
-        match *self {
-            Daemon(_) => {},
-            MasterProcess(_) => {},
-            WorkerProcesses(_) => {},
-            Http(_) => {},
-            Server(_) => {},
-            Location(_) => {},
-            LimitExcept(_) => {},
-            Listen(_) => {},
-            ProxyPass(ref mut v) => f(v),
-            ProxySetHeader { ref mut field, ref mut value } => {
-                f(field);
-                f(value);
-            }
-            ProxyMethod(ref mut v) => f(v),
-            ProxyReadTimeout(ref mut v) => f(v),
-            ProxyConnectTimeout(ref mut v) => f(v),
-            ProxyHideHeader(ref mut v) => f(v),
-            ProxyPassHeader(ref mut v) => f(v),
-            ProxyCache(ref mut v) => f(v),
-            ProxyCacheKey(ref mut v) => f(v),
-            ProxyCacheValid(self::ProxyCacheValid::Normal(ref mut v)) => f(v),
-            ProxyCacheValid(self::ProxyCacheValid::Specific(_, ref mut v)) => f(v),
-            ProxyCacheValid(self::ProxyCacheValid::Any(ref mut v)) => f(v),
-            ProxyPassRequestHeaders(_) => {},
-            ProxyPassRequestBody(_) => {},
-            ProxyHttpVersion(..) => {},
-            ProxyIgnoreHeaders(..) => {},
-            ProxyInterceptErrors(..) => {},
-            ProxyBuffering(..) => {},
-            ProxyNextUpstreamTries(ref mut v) => f(v),
-            ProxyNextUpstreamTimeout(ref mut v) => f(v),
-            ProxyNextUpstream(_) => {},
-            Gzip(_) => {},
-            GzipStatic(_) => {},
-            GzipProxied(_) => {},
-            AddHeader(self::AddHeader { ref mut field, ref mut value, .. })
-            => {
-                f(field);
-                f(value);
-            }
-            Expires(self::Expires { ref mut value, .. }) => f(value),
-            Root(ref mut v) => f(v),
-            Alias(ref mut v) => f(v),
-            ErrorPage(::ast::ErrorPage { ref mut uri, .. }) => f(uri),
-            DefaultType(ref mut v) => f(v),
-            ErrorLog { ref mut file, .. } => f(file),
-            Rewrite(ref mut rw) => f(&mut rw.replacement),
-            Return(::ast::Return::Redirect { ref mut url, .. }) => f(url),
-            Return(::ast::Return::Text { text: Some(ref mut t), .. }) => f(t),
-            Return(::ast::Return::Text { text: None, .. }) => {},
-            If(self::If { ref mut condition, .. }) => {
-                use self::IfCondition::*;
-                match condition {
-                    NonEmpty(ref mut v) => f(v),
-                    Eq(ref mut v, _) => f(v),
-                    Neq(ref mut v, _) => f(v),
-                    RegEq(ref mut v, _, _) => f(v),
-                    RegNeq(ref mut v, _, _) => f(v),
-                    Exists(ref mut v) => f(v),
-                    NotExists(ref mut v) => f(v),
-                    FileExists(ref mut v) => f(v),
-                    FileNotExists(ref mut v) => f(v),
-                    DirExists(ref mut v) => f(v),
-                    DirNotExists(ref mut v) => f(v),
-                    Executable(ref mut v) => f(v),
-                    NotExecutable(ref mut v) => f(v),
-                }
-            },
-            TryFiles(ref mut tf) => {
-                for opt in &mut tf.options {
-                    f(opt);
-                }
-                match tf.last_option {
-                    TryFilesLastOption::Uri(ref mut v) => f(v),
-                    TryFilesLastOption::NamedLocation(..) => {},
-                    TryFilesLastOption::Code(..) => {},
-                }
-            },
-            Include(ref mut v) => f(v),
-            EmptyGif => {}
-            Internal => {}
-            Etag(..) => {}
-            RecursiveErrorPages(..) => {}
-            ChunkedTransferEncoding(..) => {}
-            KeepaliveTimeout(ref mut a, ref mut b) => {
-                f(a);
-                if let Some(b) = b {
-                    f(b);
-                }
-            }
-            ServerTokens(ref mut v) => f(v),
-            SslCertificate(ref mut v) => f(v),
-            SslCertificateKey(ref mut v) => f(v),
-            ServerName(_) => {},
-            Set { ref mut value, .. } => f(value),
-            Map(::ast::Map {
-                ref mut expression,
-                ref mut default,
-                ref mut patterns,
-                ..
-            }) => {
-                f(expression);
-                if let Some(ref mut def) = default {
-                    f(def);
-                }
-                for (_, v) in patterns {
-                    f(v);
-                }
-            }
-            ClientMaxBodySize(ref mut v) => f(v),
-            // openresty
-            RewriteByLuaFile(ref mut v) => f(v),
-            BalancerByLuaFile(ref mut v) => f(v),
-            AccessByLuaFile(ref mut v) => f(v),
-            HeaderFilterByLuaFile(ref mut v) => f(v),
-            ContentByLuaFile(ref mut v) => f(v),
-            BodyFilterByLuaFile(ref mut v) => f(v),
-            LogByLuaFile(ref mut v) => f(v),
-            LuaNeedRequestBody(ref mut v) => f(v),
-            SslCertificateByLuaFile(ref mut v) => f(v),
-            SslSessionFetchByLuaFile(ref mut v) => f(v),
-            SslSessionStoreByLuaFile(ref mut v) => f(v),
-            // access
-            Allow(..) => {},
-            Deny(..) => {},
-            // log module
-            AccessLog(::ast::AccessLog::Off) => {},
-            AccessLog(::ast::AccessLog::On(ref mut lg)) => {
-                f(&mut lg.path);
-                lg.condition.as_mut().map(f);
-            },
-            // real_ip module
-            RealIpHeader(ref mut v) => f(v),
-            RealIpRecursive(..) => {},
-            SetRealIpFrom(..) => {},
-            // index module
-            Index(ref mut items) => {
-                for v in items {
-                    f(v);
+        visit_values!(self, f, mut)
+    }
+
+    /// Executes function on all the Value things (not recursively)
+    ///
+    /// Same as `visit_values_mut` but for read-only access.
+    pub(crate) fn visit_values<'a, F>(&'a self, mut f: F)
+        where F: FnMut(&'a Value)
+    {
+        visit_values!(self, f,)
+    }
+}
+
+/// Implements comparison of AST nodes ignoring their positions
+///
+/// So configs which differ only in whitespace compare equal. All fields
+/// except position must be listed.
+macro_rules! impl_eq_ignoring_position {
+    ($( $typ: ident { $( $field: ident ),+ } )+) => {
+        $(
+            impl PartialEq for $typ {
+                fn eq(&self, other: &$typ) -> bool {
+                    $( self.$field == other.$field )&&+
                 }
             }
-        }
-    }
+        )+
+    };
 }
 
+impl_eq_ignoring_position!(
+    Directive { item, leading_comments, trailing_comment }
+    Http { directives }
+    Stream { directives }
+    Server { directives }
+    If { condition, directives }
+    Location { pattern, directives }
+    LimitExcept { methods, directives }
+);
+
 impl Directive {
     /// Executes function on all the Value things (not recursively)
     ///
@@ -842,8 +1380,287 @@ impl Directive {
     }
 }
 
+impl Server {
+    /// Returns all `listen` directives of the server in order
+    pub fn listens(&self) -> Vec<&Listen> {
+        self.directives.iter().filter_map(|dir| match dir.item {
+            Item::Listen(ref lst) => Some(lst),
+            _ => None,
+        }).collect()
+    }
+
+    /// Returns `listen` directives of the server, if there are any
+    pub fn effective_listens(&self) -> EffectiveListens<'_> {
+        let listens = self.listens();
+        if listens.is_empty() {
+            EffectiveListens::Default
+        } else {
+            EffectiveListens::Explicit(listens)
+        }
+    }
+
+    /// Returns TLS directives of the server itself, in order
+    ///
+    /// These are all the directives named `ssl_*`, including ones unknown
+    /// to the parser like `ssl_protocols`. Directives inherited from the
+    /// `http` block or set in locations aren't included.
+    pub fn ssl_directives(&self) -> Vec<&Directive> {
+        self.directives.iter()
+            .filter(|dir| dir.item.keyword().starts_with("ssl_"))
+            .collect()
+    }
+}
+
+fn access_rules(dirs: &[Directive]) -> Vec<AccessRule<'_>> {
+    dirs.iter().filter_map(|dir| match dir.item {
+        Item::Allow(ref src) => Some(AccessRule::Allow(src)),
+        Item::Deny(ref src) => Some(AccessRule::Deny(src)),
+        _ => None,
+    }).collect()
+}
+
+impl LocationPattern {
+    /// Returns true for `~` and `~*` patterns
+    pub fn is_regex(&self) -> bool {
+        matches!(*self,
+            LocationPattern::Regex(..) | LocationPattern::RegexInsensitive(..))
+    }
+
+    /// Returns the path, regex or name without modifier (and without `@`)
+    pub fn text(&self) -> &str {
+        use self::LocationPattern::*;
+        match *self {
+            Prefix(ref s) | Exact(ref s) | FinalPrefix(ref s)
+            | Regex(ref s) | RegexInsensitive(ref s) | Named(ref s) => s,
+        }
+    }
+}
+
+impl Location {
+    /// Returns true if location is matched by a regex (`~` or `~*`)
+    pub fn is_regex(&self) -> bool {
+        self.pattern.is_regex()
+    }
+
+    /// Returns `allow` and `deny` rules of this location in order
+    ///
+    /// Only rules directly in this location are returned, not the ones
+    /// inherited from the server or nested locations.
+    pub fn access_rules(&self) -> Vec<AccessRule<'_>> {
+        access_rules(&self.directives)
+    }
+}
+
+impl LimitExcept {
+    /// Returns `allow` and `deny` rules applied to methods not listed
+    pub fn access_rules(&self) -> Vec<AccessRule<'_>> {
+        access_rules(&self.directives)
+    }
+}
+
+impl Upstream {
+    /// Returns sum of weights of servers receiving requests normally
+    ///
+    /// Servers without explicit `weight` have the weight of 1, as in nginx.
+    /// Servers marked as `backup` or `down` are skipped. The sum saturates
+    /// at `u32::MAX` instead of overflowing.
+    pub fn total_weight(&self) -> u32 {
+        self.servers.iter()
+            .filter(|srv| !srv.params.iter().any(|p| {
+                let p = p.to_raw_string();
+                p == "backup" || p == "down"
+            }))
+            .map(|srv| srv.weight.unwrap_or(1))
+            .fold(0, u32::saturating_add)
+    }
+}
+
+impl ErrorPage {
+    /// Returns name of the location (without `@`) if uri is `@name`
+    pub fn named_location(&self) -> Option<&str> {
+        match self.uri.data[..] {
+            [value::Item::Literal(ref x)] => x.strip_prefix('@'),
+            _ => None,
+        }
+    }
+}
+
+impl ProxyPass {
+    /// Returns true if the url has a URI part, even if it's a single `/`
+    ///
+    /// With URI part nginx replaces the part of request URI matching
+    /// location prefix by it, so `proxy_pass http://app/;` in
+    /// `location /api/` sends `/api/x` as `/x`. Without URI part, like in
+    /// `proxy_pass http://app;`, request URI is passed unchanged.
+    ///
+    /// Parts of the url referenced by variables are not looked into.
+    pub fn has_uri_component(&self) -> bool {
+        self.uri().is_some()
+    }
+
+    /// Returns the host or the unix socket requests are sent to
+    ///
+    /// Returns `None` if the url has no scheme or the host is (or
+    /// contains) a variable.
+    pub fn target(&self) -> Option<ProxyTarget> {
+        let url = self.url.to_raw_string();
+        let (target, _) = split_proxy_url(&url)?;
+        if target.contains('$') {
+            return None;
+        }
+        Some(match target.strip_prefix("unix:") {
+            Some(path) => ProxyTarget::Unix(path.to_string()),
+            None => ProxyTarget::Host(target.to_string()),
+        })
+    }
+
+    /// Returns true if requests are sent to a unix socket
+    pub fn is_unix_socket(&self) -> bool {
+        matches!(self.target(), Some(ProxyTarget::Unix(..)))
+    }
+
+    /// Returns the URI part of the url, starting with `/`
+    ///
+    /// See [`has_uri_component`](#method.has_uri_component) for what it
+    /// means for proxying.
+    pub fn uri(&self) -> Option<String> {
+        let url = self.url.to_raw_string();
+        split_proxy_url(&url)?.1.map(|uri| uri.to_string())
+    }
+}
+
+/// Splits `scheme://target/uri` into target and URI part
+///
+/// For unix sockets the URI part goes after a colon, i.e.
+/// `http://unix:/path/to/socket:/uri`.
+fn split_proxy_url(url: &str) -> Option<(&str, Option<&str>)> {
+    let rest = &url[url.find("://")?+3..];
+    let idx = match rest.strip_prefix("unix:") {
+        Some(socket) => socket.find(':').map(|idx| idx + 5),
+        None => rest.find('/'),
+    };
+    Some(match idx {
+        Some(idx) if rest.as_bytes()[idx] == b':' => {
+            (&rest[..idx], Some(&rest[idx+1..]))
+        }
+        Some(idx) => (&rest[..idx], Some(&rest[idx..])),
+        None => (rest, None),
+    })
+}
+
 impl Main {
     pub fn all_directives(&self) -> DirectiveIter {
         DirectiveIter::depth_first(&self.directives)
     }
+
+    /// Returns top-level items, without positions and leading comments
+    ///
+    /// Items are wrapped into `Directive`, so unlike the `directives` field
+    /// this can't be a slice.
+    pub fn items(&self) -> impl Iterator<Item=&Item> {
+        self.directives.iter().map(|dir| &dir.item)
+    }
+
+    /// Converts config into top-level items, dropping positions and leading
+    /// comments
+    pub fn into_items(self) -> Vec<Item> {
+        self.directives.into_iter().map(|dir| dir.item).collect()
+    }
+
+    /// Returns all `listen` directives in all `server` blocks
+    pub fn listens(&self) -> Vec<&Listen> {
+        self.all_directives().filter_map(|dir| match dir.item {
+            Item::Listen(ref lst) => Some(lst),
+            _ => None,
+        }).collect()
+    }
+
+    /// Returns all locations, including nested ones, with their servers
+    ///
+    /// Locations are in order of appearance, so all the locations of the
+    /// first server go first.
+    pub fn locations(&self) -> Vec<LocationRef<'_>> {
+        let servers = self.all_directives().filter_map(|dir| match dir.item {
+            Item::Server(ref s) => Some(s),
+            _ => None,
+        });
+        let mut result = Vec::new();
+        for (index, server) in servers.enumerate() {
+            for dir in DirectiveIter::depth_first(&server.directives) {
+                if let Item::Location(ref location) = dir.item {
+                    result.push(LocationRef { server: index, location });
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns counts of servers, locations and other things in config
+    pub fn stats(&self) -> ConfigStats {
+        fn depth(dirs: &[Directive]) -> usize {
+            dirs.iter().filter_map(|dir| dir.item.children())
+                .map(|ch| depth(ch) + 1)
+                .max().unwrap_or(0)
+        }
+        let mut stats = ConfigStats::default();
+        for dir in self.all_directives() {
+            match dir.item {
+                Item::Server(..) => stats.servers += 1,
+                Item::Location(..) => stats.locations += 1,
+                Item::Upstream(..) => stats.upstreams += 1,
+                Item::Comment(..) => continue,
+                _ => {}
+            }
+            stats.directives += 1;
+        }
+        stats.max_depth = depth(&self.directives);
+        stats
+    }
+
+    /// Returns targets of all `include` directives, in any block
+    ///
+    /// Included files are not read, targets are returned as written which
+    /// might be glob patterns or paths relative to the config directory.
+    pub fn includes(&self) -> Vec<&Value> {
+        self.all_directives().filter_map(|dir| match dir.item {
+            Item::Include(ref val) => Some(val),
+            _ => None,
+        }).collect()
+    }
+
+    /// Returns true if directive with the name is used anywhere in config
+    ///
+    /// Works both for directives known to the parser and generic ones.
+    pub fn has_directive(&self, name: &str) -> bool {
+        self.all_directives().any(|dir| dir.item.keyword() == name)
+    }
+
+    /// Returns all `set_real_ip_from` sources, i.e. trusted proxies
+    pub fn real_ip_sources(&self) -> Vec<&RealIpFrom> {
+        self.all_directives().filter_map(|dir| match dir.item {
+            Item::SetRealIpFrom(ref src) => Some(src),
+            _ => None,
+        }).collect()
+    }
+
+    /// Returns names (without `$`) of variables defined in the config
+    ///
+    /// Variables are defined by `set`, `map` and `geo`. Builtin variables
+    /// of nginx are not included. The `geo` directive is only parsed in
+    /// lenient mode, as a generic directive.
+    pub fn variables_defined(&self) -> HashSet<String> {
+        self.all_directives().filter_map(|dir| match dir.item {
+            Item::Set { ref variable, .. } => Some(variable.clone()),
+            Item::Map(ref map) => Some(map.variable.clone()),
+            Item::Generic(ref g) if g.name == "geo" => {
+                match g.args.last().map(|v| &v.data[..]) {
+                    Some([value::Item::Variable(ref name)]) => {
+                        Some(name.to_string())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }).collect()
+    }
 }
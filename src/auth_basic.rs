@@ -0,0 +1,21 @@
+use combine::{Parser};
+use combine::{choice};
+
+use ast::{Item};
+use grammar::value;
+use helpers::{semi, ident};
+use tokenizer::TokenStream;
+
+
+pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
+    choice((
+        ident("auth_basic").with(choice((
+            ident("off").map(|_| None),
+            value().map(Some),
+        )))
+            .skip(semi())
+            .map(Item::AuthBasic),
+        ident("auth_basic_user_file").with(value()).skip(semi())
+            .map(Item::AuthBasicUserFile),
+    ))
+}
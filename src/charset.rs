@@ -0,0 +1,22 @@
+use combine::{many1, Parser};
+use combine::{choice};
+
+use ast::{Item};
+use helpers::{semi, ident, string};
+use tokenizer::TokenStream;
+
+
+pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
+    choice((
+        ident("charset").with(choice((
+            ident("off").map(|_| None),
+            string().map(|t| Some(t.value.to_string())),
+        )))
+            .skip(semi())
+            .map(Item::Charset),
+        ident("charset_types")
+            .with(many1(string().map(|t| t.value.to_string())))
+            .skip(semi())
+            .map(Item::CharsetTypes),
+    ))
+}
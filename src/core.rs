@@ -1,4 +1,5 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use combine::{many, many1, Parser};
 use combine::{choice, optional};
@@ -6,7 +7,8 @@ use combine::error::StreamError;
 use combine::easy::Error;
 
 use ast::{self, Item};
-use grammar::{value, bool, block, Code};
+use grammar::{value, bool, block, duration, parse_duration, parse_size};
+use grammar::{lenient, Code};
 use helpers::{semi, ident, string, prefix};
 use tokenizer::{TokenStream, Token};
 use value::Value;
@@ -80,6 +82,7 @@ fn error_page<'a>()
 enum ListenParts {
     DefaultServer,
     Ssl,
+    Udp,
     Ext(ast::HttpExt),
     ProxyProtocol,
     SetFib(i32),
@@ -91,6 +94,8 @@ enum ListenParts {
     Bind,
     Ipv6Only(bool),
     ReusePort,
+    Multipath,
+    Extra(String),
 }
 
 fn listen<'a>()
@@ -114,14 +119,15 @@ fn listen<'a>()
     .and(many::<Vec<_>, _>(choice((
         ident("default_server").map(|_| DefaultServer),
         ident("ssl").map(|_| Ssl),
+        ident("udp").map(|_| Udp),
         ident("http2").map(|_| Ext(HttpExt::Http2)),
         ident("spdy").map(|_| Ext(HttpExt::Spdy)),
         ident("proxy_protocol").map(|_| ProxyProtocol),
         prefix("setfib=").and_then(|val| val.parse().map(SetFib)),
         prefix("fastopen=").and_then(|val| val.parse().map(FastOpen)),
         prefix("backlog=").and_then(|val| val.parse().map(Backlog)),
-        prefix("rcvbuf=").and_then(|val| val.parse().map(RcvBuf)),
-        prefix("sndbuf=").and_then(|val| val.parse().map(SndBuf)),
+        prefix("rcvbuf=").and_then(|val| parse_size(val).map(RcvBuf)),
+        prefix("sndbuf=").and_then(|val| parse_size(val).map(SndBuf)),
         ident("deferred").map(|_| Deferred),
         ident("bind").map(|_| Bind),
         prefix("ipv6only=").and_then(|val| Ok(Ipv6Only(match val {
@@ -130,6 +136,8 @@ fn listen<'a>()
             _ => return Err(Error::unexpected_message("only on/off supported")),
         }))),
         ident("reuseport").map(|_| ReusePort),
+        ident("multipath").map(|_| Multipath),
+        lenient().with(string()).map(|t| Extra(t.value.to_string())),
     ))))
     .map(|(addr, items)| {
         let mut lst = Listen::new(addr);
@@ -137,6 +145,7 @@ fn listen<'a>()
             match item {
                 DefaultServer => lst.default_server = true,
                 Ssl => lst.ssl = true,
+                Udp => lst.udp = true,
                 Ext(ext) => lst.ext = Some(ext),
                 ProxyProtocol => lst.proxy_protocol = true,
                 SetFib(v) => lst.setfib = Some(v),
@@ -148,6 +157,8 @@ fn listen<'a>()
                 Bind => lst.bind = true,
                 Ipv6Only(v) => lst.ipv6only = Some(v),
                 ReusePort => lst.reuseport = true,
+                Multipath => lst.multipath = true,
+                Extra(opt) => lst.extra.push(opt),
             }
         }
         return lst;
@@ -167,6 +178,78 @@ fn limit_except<'a>()
     })
 }
 
+fn types<'a>()
+    -> impl Parser<Output=Item, Input=TokenStream<'a>>
+{
+    use tokenizer::Kind::{BlockStart, BlockEnd, Comment};
+    use helpers::kind;
+    ident("types")
+    .skip(kind(BlockStart))
+    .with(many(choice((
+        kind(Comment).map(|_| None),
+        string().and(many1(string())).skip(semi())
+            .map(|(mime, exts): (Token, Vec<Token>)| Some(ast::TypeEntry {
+                mime: mime.value.to_string(),
+                extensions: exts.iter().map(|e| e.value.to_string()).collect(),
+            })),
+    ))))
+    .skip(kind(BlockEnd))
+    .map(|entries: Vec<Option<_>>| {
+        Item::Types(entries.into_iter().flatten().collect())
+    })
+}
+
+fn open_file_cache<'a>()
+    -> impl Parser<Output=Item, Input=TokenStream<'a>>
+{
+    use ast::OpenFileCache::*;
+    enum Param {
+        Max(u64),
+        Inactive(Duration),
+    }
+    ident("open_file_cache").with(choice((
+        ident("off").map(|_| Off),
+        many1(choice((
+            prefix("max=").and_then(|val| val.parse()).map(Param::Max),
+            prefix("inactive=").and_then(parse_duration).map(Param::Inactive),
+        ))).and_then(|params: Vec<Param>| {
+            let mut max = None;
+            let mut inactive = None;
+            for param in params {
+                let duplicate = match param {
+                    Param::Max(m) => max.replace(m).is_some(),
+                    Param::Inactive(d) => inactive.replace(d).is_some(),
+                };
+                if duplicate {
+                    return Err(Error::unexpected_message(
+                        "duplicate open_file_cache parameter"));
+                }
+            }
+            match max {
+                Some(max) => Ok(Cache { max, inactive }),
+                None => Err(Error::unexpected_message(
+                    "open_file_cache requires max=")),
+            }
+        }),
+    )))
+    .skip(semi())
+    .map(Item::OpenFileCache)
+}
+
+fn server_tokens<'a>()
+    -> impl Parser<Output=Item, Input=TokenStream<'a>>
+{
+    use ast::ServerTokens::*;
+    ident("server_tokens").with(choice((
+        ident("on").map(|_| On),
+        ident("off").map(|_| Off),
+        ident("build").map(|_| Build),
+        value().map(Custom),
+    )))
+    .skip(semi())
+    .map(Item::ServerTokens)
+}
+
 pub fn directives<'a>()
     -> impl Parser<Output=Item, Input=TokenStream<'a>>
 {
@@ -174,23 +257,38 @@ pub fn directives<'a>()
         error_page(),
         listen(),
         limit_except(),
+        types(),
         ident("root").with(value()).skip(semi()).map(Item::Root),
         ident("alias").with(value()).skip(semi()).map(Item::Alias),
         ident("default_type").with(value()).skip(semi())
             .map(Item::DefaultType),
         ident("internal").skip(semi()).map(|_| Item::Internal),
         ident("etag").with(bool()).skip(semi()).map(Item::Etag),
-        ident("server_tokens").with(value()).skip(semi())
-            .map(Item::ServerTokens),
+        ident("merge_slashes").with(bool()).skip(semi())
+            .map(Item::MergeSlashes),
+        ident("absolute_redirect").with(bool()).skip(semi())
+            .map(Item::AbsoluteRedirect),
+        ident("port_in_redirect").with(bool()).skip(semi())
+            .map(Item::PortInRedirect),
+        server_tokens(),
+        open_file_cache(),
         ident("recursive_error_pages").with(bool()).skip(semi())
             .map(Item::RecursiveErrorPages),
         ident("chunked_transfer_encoding").with(bool()).skip(semi())
             .map(Item::ChunkedTransferEncoding),
+        ident("log_not_found").with(bool()).skip(semi())
+            .map(Item::LogNotFound),
         ident("keepalive_timeout")
             .with(value())
             .and(optional(value()))
             .map(|(timeo, htimeo)| Item::KeepaliveTimeout(timeo, htimeo))
             .skip(semi()),
+        ident("client_body_timeout").with(duration()).skip(semi())
+            .map(Item::ClientBodyTimeout),
+        ident("client_header_timeout").with(duration()).skip(semi())
+            .map(Item::ClientHeaderTimeout),
+        ident("send_timeout").with(duration()).skip(semi())
+            .map(Item::SendTimeout),
         ident("error_log").with(value())
             .and(optional(string().and_then(|t| {
                 use ast::ErrorLevel::*;
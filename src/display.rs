@@ -1,20 +1,32 @@
 use ast;
+use std::borrow::Cow;
 use std::fmt;
+use std::io;
+use std::time::Duration;
 use format::{Displayable, Formatter, Style};
 
 use value;
 
 impl Displayable for ast::Main {
     fn display(&self, f: &mut Formatter) {
-        for item in &self.directives {
-            item.display(f);
-        }
+        directives(f, &self.directives);
     }
 }
 
 impl Displayable for ast::Directive {
     fn display(&self, f: &mut Formatter) {
-        self.item.display(f)
+        if f.minify() && matches!(self.item, ast::Item::Comment(..)) {
+            return;
+        }
+        f.leading_comments(&self.leading_comments);
+        match self.trailing_comment {
+            Some(ref comment) => {
+                let token = f.trailing_comment(comment);
+                self.item.display(f);
+                f.forget_trailing_comment(token);
+            }
+            None => self.item.display(f),
+        }
     }
 }
 
@@ -25,10 +37,91 @@ fn simple_block<D: fmt::Display>(f: &mut Formatter, name: D,
     f.indent();
     f.fmt(&format_args!("{} ", name));
     f.start_block();
-    for dir in directives {
+    self::directives(f, directives);
+    f.end_block();
+}
+
+fn keeps_position(item: &ast::Item) -> bool {
+    use ast::Item::*;
+    matches!(*item, Rewrite(..) | Return(..) | If(..) | Allow(..) |
+        Deny(..) | Include(..) | Generic(..) | Comment(..))
+}
+
+fn sorted_run(f: &mut Formatter, run: &mut Vec<&ast::Directive>) {
+    run.sort_by_key(|d| d.item.keyword());
+    for dir in run.drain(..) {
         dir.display(f);
     }
-    f.end_block();
+}
+
+fn directives(f: &mut Formatter, directives: &[ast::Directive]) {
+    if !f.sort_directives() {
+        for dir in directives {
+            dir.display(f);
+        }
+        return;
+    }
+    let mut run = Vec::new();
+    for dir in directives {
+        if keeps_position(&dir.item) {
+            sorted_run(f, &mut run);
+            dir.display(f);
+        } else {
+            run.push(dir);
+        }
+    }
+    sorted_run(f, &mut run);
+}
+
+fn duration(val: &Duration, f: &mut Formatter) {
+    const UNITS: &[(&str, u64)] = &[
+        ("d", 86_400_000),
+        ("h", 3_600_000),
+        ("m", 60_000),
+        ("s", 1000),
+        ("ms", 1),
+    ];
+    let mut ms = val.as_secs()*1000 + u64::from(val.subsec_millis());
+    if ms == 0 {
+        f.write("0s");
+        return;
+    }
+    for &(unit, scale) in UNITS {
+        if ms >= scale {
+            f.fmt(&(ms / scale));
+            f.write(unit);
+            ms %= scale;
+        }
+    }
+}
+
+fn size(val: u64, f: &mut Formatter) {
+    const UNITS: &[(&str, u64)] = &[
+        ("g", 1 << 30),
+        ("m", 1 << 20),
+        ("k", 1 << 10),
+    ];
+    for &(unit, scale) in UNITS {
+        if val != 0 && val.is_multiple_of(scale) {
+            f.fmt(&(val / scale));
+            f.write(unit);
+            return;
+        }
+    }
+    f.fmt(&val);
+}
+
+/// Returns a regex, quoted if it contains chars special for the config
+fn quote_regex(val: &str) -> Cow<'_, str> {
+    let special = val.is_empty() || val.contains(|c| matches!(c,
+        ' ' | ';' | '{' | '}' | '\'' | '"' | '\t' | '\r' | '\n'));
+    if !special {
+        Cow::Borrowed(val)
+    } else if val.contains('"') && !val.contains('\'') {
+        Cow::Owned(format!("'{}'", val))
+    } else {
+        Cow::Owned(format!("\"{}\"", value::escape_double_quotes(val)))
+    }
 }
 
 fn one_arg_dir(name: &str, val: &value::Value, f: &mut Formatter) {
@@ -51,8 +144,14 @@ impl Displayable for ast::Item {
             | ProxyBuffering(opt)
             | Gzip(opt)
             | Etag(opt)
+            | MergeSlashes(opt)
+            | AbsoluteRedirect(opt)
+            | PortInRedirect(opt)
+            | SslPreferServerCiphers(opt)
             | RecursiveErrorPages(opt)
             | ChunkedTransferEncoding(opt)
+            | LogNotFound(opt)
+            | RewriteLog(opt)
             | RealIpRecursive(opt)
             => {
                 f.indent();
@@ -72,17 +171,10 @@ impl Displayable for ast::Item {
                 f.fmt(&n);
                 f.end();
             }
-            Http(ref h) => {
-                simple_block(f, "http", &h.directives);
-            }
-            Server(ref s) => {
-                simple_block(f, "server", &s.directives);
-            }
-            Location(ast::Location { ref pattern, ref directives, .. }) => {
-                simple_block(f,
-                    format_args!("location {}", pattern),
-                    &directives);
-            }
+            Http(ref h) => h.display(f),
+            Stream(ref s) => s.display(f),
+            Server(ref s) => s.display(f),
+            Location(ref loc) => loc.display(f),
             LimitExcept(ast::LimitExcept { ref methods, ref directives, .. })
             => {
                 simple_block(f,
@@ -93,12 +185,53 @@ impl Displayable for ast::Item {
                 f.indent();
                 lst.display(f);
             }
-            ProxySetHeader { ref field, ref value } => {
+            ProxySetHeader(ref h) => {
                 f.indent();
                 f.write("proxy_set_header ");
-                field.display(f);
-                f.write(" ");
-                value.display(f);
+                h.display(f);
+                f.end();
+            }
+            FastcgiParam { ref param, if_not_empty } => {
+                f.indent();
+                f.write("fastcgi_param ");
+                param.display(f);
+                if if_not_empty {
+                    f.write(" if_not_empty");
+                }
+                f.end();
+            }
+            OpenFileCache(ref val) => {
+                use ast::OpenFileCache::*;
+                f.indent();
+                f.write("open_file_cache");
+                match *val {
+                    Off => f.write(" off"),
+                    Cache { max, ref inactive } => {
+                        f.fmt(&format_args!(" max={}", max));
+                        if let Some(ref inactive) = *inactive {
+                            f.write(" inactive=");
+                            duration(inactive, f);
+                        }
+                    }
+                }
+                f.end();
+            }
+            ServerTokens(ref val) => {
+                use ast::ServerTokens::*;
+                f.indent();
+                f.write("server_tokens ");
+                match *val {
+                    On => f.write("on"),
+                    Off => f.write("off"),
+                    Build => f.write("build"),
+                    Custom(ref v) => v.display(f),
+                }
+                f.end();
+            }
+            Satisfy(opt) => {
+                f.indent();
+                f.write("satisfy ");
+                f.write(opt.as_str());
                 f.end();
             }
             GzipStatic(opt) => {
@@ -107,6 +240,26 @@ impl Displayable for ast::Item {
                 f.write(opt.as_str());
                 f.end();
             }
+            GzipTypes(ref types) => {
+                f.indent();
+                f.write("gzip_types");
+                for t in types {
+                    f.write(" ");
+                    f.write(escape(t));
+                }
+                f.end();
+            }
+            GzipCompLevel(level) => {
+                f.indent();
+                f.fmt(&format_args!("gzip_comp_level {}", level));
+                f.end();
+            }
+            GzipMinLength(len) => {
+                f.indent();
+                f.write("gzip_min_length ");
+                size(len, f);
+                f.end();
+            }
             GzipProxied(ref opt) => {
                 f.indent();
                 f.write("gzip_proxied");
@@ -116,12 +269,11 @@ impl Displayable for ast::Item {
                 }
                 f.end();
             }
-            AddHeader(ref h) => {
+            AddHeader(ref h) | AddTrailer(ref h) => {
                 f.indent();
-                f.write("add_header ");
-                h.field.display(f);
+                f.write(self.directive_name());
                 f.write(" ");
-                h.value.display(f);
+                h.header.display(f);
                 if h.always {
                     f.write(" always");
                 }
@@ -205,7 +357,7 @@ impl Displayable for ast::Item {
                         StarPrefix(ref v)
                         => f.fmt(&format_args!("{}.*", escape(&v))),
                         Regex(ref v)
-                        => f.fmt(&format_args!("~{}", escape(&v))),
+                        => f.write(&quote_regex(&format!("~{}", v))),
                     }
                     f.write(" ");
                     value.display(f);
@@ -213,11 +365,89 @@ impl Displayable for ast::Item {
                 }
                 f.end_block();
             }
+            Types(ref entries) => {
+                f.margin();
+                f.indent();
+                f.write("types ");
+                f.start_block();
+                for entry in entries {
+                    f.indent();
+                    f.write(escape(&entry.mime));
+                    for ext in &entry.extensions {
+                        f.write(" ");
+                        f.write(escape(ext));
+                    }
+                    f.end();
+                }
+                f.end_block();
+            }
+            Upstream(ref u) => {
+                use ast::BalancingMethod::*;
+                f.margin();
+                f.indent();
+                f.write("upstream ");
+                f.write(escape(&u.name));
+                f.write(" ");
+                f.start_block();
+                match u.balancing {
+                    Some(LeastConn) => {
+                        f.indent();
+                        f.write("least_conn");
+                        f.end();
+                    }
+                    Some(IpHash) => {
+                        f.indent();
+                        f.write("ip_hash");
+                        f.end();
+                    }
+                    Some(Hash { ref key, consistent }) => {
+                        f.indent();
+                        f.write("hash ");
+                        key.display(f);
+                        if consistent {
+                            f.write(" consistent");
+                        }
+                        f.end();
+                    }
+                    None => {}
+                }
+                for srv in &u.servers {
+                    f.indent();
+                    f.write("server ");
+                    srv.address.display(f);
+                    if let Some(w) = srv.weight {
+                        f.fmt(&format_args!(" weight={}", w));
+                    }
+                    for param in &srv.params {
+                        f.write(" ");
+                        param.display(f);
+                    }
+                    f.end();
+                }
+                if let Some(n) = u.keepalive {
+                    f.indent();
+                    f.fmt(&format_args!("keepalive {}", n));
+                    f.end();
+                }
+                if let Some(n) = u.keepalive_requests {
+                    f.indent();
+                    f.fmt(&format_args!("keepalive_requests {}", n));
+                    f.end();
+                }
+                if let Some(ref t) = u.keepalive_timeout {
+                    f.indent();
+                    f.write("keepalive_timeout ");
+                    duration(t, f);
+                    f.end();
+                }
+                self::directives(f, &u.directives);
+                f.end_block();
+            }
             Rewrite(ref rw) => {
                 use ast::RewriteFlag::*;
                 f.indent();
                 f.write("rewrite ");
-                f.write(escape(&rw.regex));
+                f.write(&quote_regex(&rw.regex));
                 f.write(" ");
                 rw.replacement.display(f);
                 f.write(match rw.flag {
@@ -247,21 +477,38 @@ impl Displayable for ast::Item {
             | SslSessionStoreByLuaFile(ref val)
             | SslCertificate(ref val)
             | SslCertificateKey(ref val)
-            | ProxyPass(ref val)
+            | SslDhparam(ref val)
+            | SslEcdhCurve(ref val)
             | ProxyCache(ref val)
             | ProxyCacheKey(ref val)
             | ProxyMethod(ref val)
-            | ProxyReadTimeout(ref val)
-            | ProxyConnectTimeout(ref val)
             | ProxyHideHeader(ref val)
             | ProxyPassHeader(ref val)
             | ProxyNextUpstreamTries(ref val)
             | ProxyNextUpstreamTimeout(ref val)
-            | ServerTokens(ref val)
             | RealIpHeader(ref val)
+            | AuthBasicUserFile(ref val)
+            | GeoipCountry(ref val)
+            | GeoipCity(ref val)
+            | GeoipOrg(ref val)
+            | FastcgiPass(ref val)
+            | FastcgiIndex(ref val)
             => {
                 one_arg_dir(self.directive_name(), val, f);
             }
+            | ProxyReadTimeout(ref val)
+            | ProxyConnectTimeout(ref val)
+            | ProxySendTimeout(ref val)
+            | ClientBodyTimeout(ref val)
+            | ClientHeaderTimeout(ref val)
+            | SendTimeout(ref val)
+            => {
+                f.indent();
+                f.write(self.directive_name());
+                f.write(" ");
+                duration(val, f);
+                f.end();
+            }
             | EmptyGif
             | Internal
             => {
@@ -311,6 +558,9 @@ impl Displayable for ast::Item {
                 }
                 f.end()
             }
+            ProxyPass(ref p) => {
+                one_arg_dir(self.directive_name(), &p.url, f);
+            }
             TryFiles(ref tf) => {
                 use ast::TryFilesLastOption::*;
                 f.indent();
@@ -341,80 +591,7 @@ impl Displayable for ast::Item {
                 value.display(f);
                 f.end();
             }
-            If(ast::If { ref condition, ref directives, position: _ }) => {
-                use ast::IfCondition::*;
-                f.indent();
-                f.write("if (");
-                match condition {
-                    NonEmpty(ref v) => v.display(f),
-                    Eq(ref v, ref s) => {
-                        v.display(f);
-                        f.write(" = ");
-                        f.write(&escape(s));
-                    }
-                    Neq(ref v, ref s) => {
-                        v.display(f);
-                        f.write(" != ");
-                        f.write(&escape(s));
-                    }
-                    RegEq(ref v, ref r, case) => {
-                        v.display(f);
-                        if *case {
-                            f.write(" ~ ");
-                        } else {
-                            f.write(" ~* ");
-                        }
-                        f.write(&escape(r));
-                    }
-                    RegNeq(ref v, ref r, case) => {
-                        v.display(f);
-                        if *case {
-                            f.write(" !~ ");
-                        } else {
-                            f.write(" !~* ");
-                        }
-                        f.write(&escape(r));
-                    }
-                    Exists(ref v) => {
-                        f.write("-e ");
-                        v.display(f);
-                    }
-                    NotExists(ref v) => {
-                        f.write("!-e ");
-                        v.display(f);
-                    },
-                    FileExists(ref v) => {
-                        f.write("-f ");
-                        v.display(f);
-                    },
-                    FileNotExists(ref v) => {
-                        f.write("!-f ");
-                        v.display(f);
-                    },
-                    DirExists(ref v) => {
-                        f.write("-d ");
-                        v.display(f);
-                    },
-                    DirNotExists(ref v) => {
-                        f.write("!-d ");
-                        v.display(f);
-                    },
-                    Executable(ref v) => {
-                        f.write("-x ");
-                        v.display(f);
-                    },
-                    NotExecutable(ref v) => {
-                        f.write("!-x ");
-                        v.display(f);
-                    },
-                }
-                f.write(") ");
-                f.start_block();
-                for dir in directives {
-                    dir.display(f);
-                }
-                f.end_block();
-            }
+            If(ref i) => i.display(f),
             Allow(ref source) | Deny(ref source) => {
                 use ast::Source::*;
                 f.indent();
@@ -432,6 +609,28 @@ impl Displayable for ast::Item {
                 }
                 f.end();
             }
+            ProxyRedirect(ref redir) => {
+                use ast::ProxyRedirect::*;
+                f.indent();
+                f.write("proxy_redirect ");
+                match *redir {
+                    Default => f.write("default"),
+                    Off => f.write("off"),
+                    Redirect { ref redirect, ref replacement } => {
+                        redirect.display(f);
+                        f.write(" ");
+                        replacement.display(f);
+                    }
+                    Regex { ref pattern, case_insensitive, ref replacement }
+                    => {
+                        let op = if case_insensitive { "~*" } else { "~" };
+                        f.write(&quote_regex(&format!("{}{}", op, pattern)));
+                        f.write(" ");
+                        replacement.display(f);
+                    }
+                }
+                f.end();
+            }
             ProxyHttpVersion(ver) => {
                 use ast::ProxyHttpVersion::*;
                 f.indent();
@@ -457,7 +656,7 @@ impl Displayable for ast::Item {
                 match val {
                     Normal(ref val) => {
                         f.write(" ");
-                        val.display(f);
+                        duration(val, f);
                     }
                     Specific(ref codes, ref val) => {
                         for code in codes {
@@ -465,11 +664,11 @@ impl Displayable for ast::Item {
                             f.fmt(&code);
                         }
                         f.write(" ");
-                        val.display(f);
+                        duration(val, f);
                     }
                     Any(ref val) => {
                         f.write(" any ");
-                        val.display(f);
+                        duration(val, f);
                     }
                 }
                 f.end();
@@ -543,6 +742,53 @@ impl Displayable for ast::Item {
                 }
                 f.end();
             }
+            Charset(ref charset) => {
+                f.indent();
+                f.write("charset ");
+                match charset {
+                    Some(ref name) => f.fmt(&escape(name)),
+                    None => f.write("off"),
+                }
+                f.end();
+            }
+            AuthBasic(Some(ref realm)) => {
+                one_arg_dir(self.directive_name(), realm, f);
+            }
+            AuthBasic(None) => {
+                f.indent();
+                f.write("auth_basic off");
+                f.end();
+            }
+            CharsetTypes(ref types) => {
+                f.indent();
+                f.write(self.directive_name());
+                for t in types {
+                    f.write(" ");
+                    f.fmt(&escape(t));
+                }
+                f.end();
+            }
+            LogFormat(ref lf) => {
+                f.indent();
+                f.write("log_format ");
+                f.fmt(&escape(&lf.name));
+                if let Some(ref escape_mode) = lf.escape {
+                    f.write(" escape=");
+                    f.fmt(&escape(escape_mode));
+                }
+                f.write(" ");
+                // formats usually have bare `"` inside, so single quotes
+                // are preferred like in `quote_regex`
+                let raw = lf.template.to_raw_string();
+                if raw.contains('"') && !raw.contains(['\'', '\\']) {
+                    f.write("'");
+                    f.write(&raw);
+                    f.write("'");
+                } else {
+                    lf.template.display(f);
+                }
+                f.end();
+            }
             SetRealIpFrom(ref source) => {
                 use ast::RealIpFrom::*;
                 f.indent();
@@ -559,6 +805,29 @@ impl Displayable for ast::Item {
                 }
                 f.end();
             }
+            SslSessionCache(ref cache) => {
+                use ast::SslSessionCache as C;
+                use ast::SslSessionCacheKind::*;
+                f.indent();
+                f.write("ssl_session_cache");
+                match *cache {
+                    C::Off => f.write(" off"),
+                    C::None => f.write(" none"),
+                    C::Caches(ref caches) => for item in caches {
+                        match *item {
+                            Builtin(None) => f.write(" builtin"),
+                            Builtin(Some(n)) => {
+                                f.fmt(&format_args!(" builtin:{}", n));
+                            }
+                            Shared { ref name, size: bytes } => {
+                                f.fmt(&format_args!(" shared:{}:", name));
+                                size(bytes, f);
+                            }
+                        }
+                    },
+                }
+                f.end();
+            }
             ErrorLog { ref file, level } => {
                 f.indent();
                 f.write(self.directive_name());
@@ -589,16 +858,124 @@ impl Displayable for ast::Item {
                 }
                 f.end();
             }
+            Generic(ast::Generic { ref name, ref args, block: None }) => {
+                f.indent();
+                f.write(name);
+                for arg in args {
+                    f.write(" ");
+                    arg.display(f);
+                }
+                f.end();
+            }
+            Generic(ast::Generic { ref name, ref args, block: Some(ref dirs) })
+            => {
+                let mut header = name.clone();
+                for arg in args {
+                    header.push(' ');
+                    header.push_str(&arg.to_string());
+                }
+                simple_block(f, header, dirs);
+            }
+            Comment(ref c) => {
+                f.indent();
+                f.write("#");
+                f.write(&c.text);
+                f.endline();
+            }
+        }
+    }
+}
+
+impl Displayable for ast::Http {
+    fn display(&self, f: &mut Formatter) {
+        simple_block(f, "http", &self.directives);
+    }
+}
+
+impl Displayable for ast::Stream {
+    fn display(&self, f: &mut Formatter) {
+        simple_block(f, "stream", &self.directives);
+    }
+}
+
+impl Displayable for ast::Server {
+    fn display(&self, f: &mut Formatter) {
+        simple_block(f, "server", &self.directives);
+    }
+}
+
+impl Displayable for ast::Location {
+    fn display(&self, f: &mut Formatter) {
+        simple_block(f,
+            format_args!("location {}", self.pattern),
+            &self.directives);
+    }
+}
+
+impl Displayable for ast::IfCondition {
+    fn display(&self, f: &mut Formatter) {
+        use ast::IfCondition::*;
+        match *self {
+            NonEmpty(ref v) => v.display(f),
+            Eq(ref v, ref s) => {
+                v.display(f);
+                f.write(" = ");
+                s.display(f);
+            }
+            Neq(ref v, ref s) => {
+                v.display(f);
+                f.write(" != ");
+                s.display(f);
+            }
+            Regex { ref variable, ref pattern, negated,
+                    case_insensitive }
+            => {
+                variable.display(f);
+                f.write(match (negated, case_insensitive) {
+                    (false, false) => " ~ ",
+                    (false, true) => " ~* ",
+                    (true, false) => " !~ ",
+                    (true, true) => " !~* ",
+                });
+                f.write(&quote_regex(pattern));
+            }
+            FileTest { negated, kind, ref operand } => {
+                use ast::FileTestKind::*;
+                if negated {
+                    f.write("!");
+                }
+                f.write(match kind {
+                    File => "-f ",
+                    Dir => "-d ",
+                    Exists => "-e ",
+                    Executable => "-x ",
+                });
+                operand.display(f);
+            }
         }
     }
 }
 
+impl Displayable for ast::If {
+    fn display(&self, f: &mut Formatter) {
+        let ast::If { ref condition, ref directives, .. } = *self;
+        f.indent();
+        f.write("if (");
+        condition.display(f);
+        f.write(") ");
+        f.start_block();
+        self::directives(f, directives);
+        f.end_block();
+    }
+}
+
 impl Displayable for ast::Listen {
     fn display(&self, f: &mut Formatter) {
         f.write("listen ");
         self.address.display(f);
         if self.default_server { f.write(" default_server") }
         if self.ssl { f.write(" ssl") }
+        if self.udp { f.write(" udp") }
         match self.ext {
             Some(ast::HttpExt::Http2) => f.write(" http2"),
             Some(ast::HttpExt::Spdy) => f.write(" spdy"),
@@ -615,10 +992,12 @@ impl Displayable for ast::Listen {
             f.fmt(&format_args!(" backlog={}", backlog));
         }
         if let Some(rcvbuf) = self.rcvbuf {
-            f.fmt(&format_args!(" rcvbuf={}", rcvbuf));
+            f.write(" rcvbuf=");
+            size(rcvbuf, f);
         }
         if let Some(sndbuf) = self.sndbuf {
-            f.fmt(&format_args!(" sndbuf={}", sndbuf));
+            f.write(" sndbuf=");
+            size(sndbuf, f);
         }
         if self.deferred { f.write(" deferred") }
         if self.bind { f.write(" bind") }
@@ -627,10 +1006,23 @@ impl Displayable for ast::Listen {
                                if ipv6only { "on" } else { "off" }));
         }
         if self.reuseport { f.write(" reuseport") }
+        if self.multipath { f.write(" multipath") }
+        for opt in &self.extra {
+            f.write(" ");
+            f.write(opt);
+        }
         f.end();
     }
 }
 
+impl Displayable for ast::NameValue {
+    fn display(&self, f: &mut Formatter) {
+        self.name.display(f);
+        f.write(" ");
+        self.value.display(f);
+    }
+}
+
 impl Displayable for ast::Address {
     fn display(&self, f: &mut Formatter) {
         use ast::Address::*;
@@ -644,11 +1036,77 @@ impl Displayable for ast::Address {
     }
 }
 
-fn to_string<T: Displayable>(v: &T) -> String {
-    let style = Style::default();
-    let mut formatter = Formatter::new(&style);
+fn write_with<T: Displayable>(v: &T, out: &mut dyn fmt::Write,
+    style: &Style)
+    -> fmt::Result
+{
+    let mut formatter = Formatter::new(out, style);
     v.display(&mut formatter);
-    formatter.into_string()
+    formatter.finish()
+}
+
+impl ast::Main {
+    /// Format configuration using the specified style
+    ///
+    /// `to_string()` is the same as this method with `Style::default()`.
+    pub fn to_string_with(&self, style: &Style) -> String {
+        let mut buf = String::with_capacity(1024);
+        write_with(self, &mut buf, style)
+            .expect("writing to string never fails");
+        buf
+    }
+
+    /// Format configuration on a single line without comments
+    ///
+    /// This is a shortcut for `to_string_with` and `Style::minify`. Output
+    /// parses back to the same config, except for comments, so it's useful
+    /// for hashing or comparing configs.
+    pub fn render_minified(&self) -> String {
+        let mut style = Style::default();
+        style.minify(true);
+        self.to_string_with(&style)
+    }
+
+    /// Write formatted configuration into `out` using the specified style
+    ///
+    /// Output is written piece by piece, without formatting whole config
+    /// into `String` first. The first error returned by `out` stops
+    /// formatting and is returned.
+    pub fn write_to<W: fmt::Write>(&self, out: &mut W, style: &Style)
+        -> fmt::Result
+    {
+        write_with(self, out, style)
+    }
+}
+
+/// Adapts `io::Write` to `fmt::Write`, keeping the actual error
+struct IoWriter<W> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+impl ast::Main {
+    /// Write formatted configuration into an `io::Write` with default style
+    ///
+    /// Output isn't buffered here, so wrap a file into `io::BufWriter`.
+    pub fn to_writer<W: io::Write>(&self, out: W) -> io::Result<()> {
+        let mut writer = IoWriter { out, error: None };
+        match write_with(self, &mut writer, &Style::default()) {
+            Ok(()) => writer.out.flush(),
+            Err(_) => Err(writer.error.take().unwrap_or_else(|| {
+                io::Error::other("formatter error")
+            })),
+        }
+    }
 }
 
 macro_rules! impl_display {
@@ -656,7 +1114,7 @@ macro_rules! impl_display {
         $(
             impl fmt::Display for $typ {
                 fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                    f.write_str(&to_string(self))
+                    write_with(self, f, &Style::default())
                 }
             }
         )+
@@ -665,8 +1123,15 @@ macro_rules! impl_display {
 
 impl_display!(
     ast::Main,
+    ast::Http,
+    ast::Stream,
+    ast::Server,
+    ast::Location,
+    ast::If,
+    ast::IfCondition,
     ast::Listen,
     ast::Address,
+    ast::NameValue,
     ast::Directive,
     ast::Item,
     value::Value,
@@ -684,8 +1149,8 @@ impl fmt::Display for ast::LocationPattern {
             Prefix(ref p) => f.write_str(escape(p)),
             Exact(ref p) => write!(f, "= {}", escape(p)),
             FinalPrefix(ref p) => write!(f, "^~ {}", escape(p)),
-            Regex(ref p) => write!(f, "~ {}", escape(p)),
-            RegexInsensitive(ref p) => write!(f, "~* {}", escape(p)),
+            Regex(ref p) => write!(f, "~ {}", quote_regex(p)),
+            RegexInsensitive(ref p) => write!(f, "~* {}", quote_regex(p)),
             Named(ref name) => {
                 write!(f, "{}", escape(&(String::from("@") + name)))
             }
@@ -704,6 +1169,16 @@ impl ast::GzipStatic {
     }
 }
 
+impl ast::Satisfy {
+    fn as_str(&self) -> &str {
+        use ast::Satisfy::*;
+        match *self {
+            All => "all",
+            Any => "any",
+        }
+    }
+}
+
 impl ast::GzipProxied {
     fn as_str(&self) -> &str {
         use ast::GzipProxied::*;
@@ -728,6 +1203,12 @@ impl fmt::Display for ast::GzipStatic {
     }
 }
 
+impl fmt::Display for ast::Satisfy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
 impl fmt::Display for ast::GzipProxied {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.as_str().fmt(f)
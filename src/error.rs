@@ -1,3 +1,6 @@
+use std::error;
+use std::fmt;
+
 use combine::easy::{Errors, Error};
 
 use tokenizer::Token;
@@ -10,10 +13,17 @@ pub type InternalError<'a> = Errors<Token<'a>, Token<'a>, Pos>;
 ///
 /// This structure is opaque for forward compatibility. We are exploring a
 /// way to improve both error message and API.
-#[derive(Fail, Debug)]
-#[fail(display="parse error: {}", _0)]
+#[derive(Debug)]
 pub struct ParseError(Errors<String, String, Pos>);
 
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "parse error: {}", self.0)
+    }
+}
+
+impl error::Error for ParseError {}
+
 #[cfg(not(feature="fuzzy_errors"))]
 impl<'a> From<InternalError<'a>> for ParseError {
     fn from(e: InternalError<'a>) -> ParseError {
@@ -0,0 +1,24 @@
+use combine::{Parser};
+use combine::{choice, optional};
+
+use ast::{Item};
+use grammar::{name_value, value};
+use helpers::{semi, ident};
+use tokenizer::TokenStream;
+
+
+pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
+    choice((
+        ident("fastcgi_pass").with(value()).skip(semi())
+            .map(Item::FastcgiPass),
+        ident("fastcgi_param").with(name_value())
+            .and(optional(ident("if_not_empty")))
+            .skip(semi())
+            .map(|(param, flag)| Item::FastcgiParam {
+                param,
+                if_not_empty: flag.is_some(),
+            }),
+        ident("fastcgi_index").with(value()).skip(semi())
+            .map(Item::FastcgiIndex),
+    ))
+}
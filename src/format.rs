@@ -1,27 +1,43 @@
 use std::fmt;
+use std::mem;
 use std::default::Default;
 
 
-#[derive(Debug, PartialEq)]
+/// Writes formatted config into any `fmt::Write`
+///
+/// Errors of the sink are remembered, and the rest of the output is
+/// skipped, see `finish()`.
 pub(crate) struct Formatter<'a> {
-    buf: String,
+    out: &'a mut dyn fmt::Write,
     style: &'a Style,
     indent: u32,
+    comments: Vec<String>,
+    /// Trailing comments of the directives being written, with indent
+    trailing: Vec<(u32, String)>,
+    /// Last two chars written, `\0` if nothing is written yet
+    tail: [char; 2],
+    /// Trailing space not written yet, only used when minifying
+    space: bool,
+    result: fmt::Result,
 }
 
 /// A configuration of formatting style
 ///
-/// Currently we only have indentation configured, other things might be
-/// added later.
+/// Currently we only have indentation, directive order and minification
+/// configured, other things might be added later.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Style {
     indent: u32,
+    sort_directives: bool,
+    minify: bool,
 }
 
 impl Default for Style {
     fn default() -> Style {
         Style {
             indent: 4,
+            sort_directives: false,
+            minify: false,
         }
     }
 }
@@ -32,6 +48,29 @@ impl Style {
         self.indent = indent;
         self
     }
+
+    /// Sort directives within each block alphabetically by name
+    ///
+    /// Sort is stable, so directives of the same name (e.g. `location`
+    /// blocks) keep their relative order. Leading comments are moved
+    /// together with the directive.
+    ///
+    /// Directives whose position is significant (`rewrite`, `return`,
+    /// `if`, `allow`, `deny`, `include`, unknown directives and commented
+    /// out lines) are never moved, only directives between them are sorted.
+    pub fn sort_directives(&mut self, sort: bool) -> &mut Self {
+        self.sort_directives = sort;
+        self
+    }
+
+    /// Write the config in as few chars as possible
+    ///
+    /// All newlines, indentation and comments are omitted, so the config
+    /// is written on a single line. Indentation setting is ignored then.
+    pub fn minify(&mut self, minify: bool) -> &mut Self {
+        self.minify = minify;
+        self
+    }
 }
 
 pub(crate) trait Displayable {
@@ -39,30 +78,146 @@ pub(crate) trait Displayable {
 }
 
 impl<'a> Formatter<'a> {
-    pub fn new(style: &Style) -> Formatter {
+    pub fn new(out: &'a mut dyn fmt::Write, style: &'a Style)
+        -> Formatter<'a>
+    {
         Formatter {
-            buf: String::with_capacity(1024),
+            out,
             style,
             indent: 0,
+            comments: Vec::new(),
+            trailing: Vec::new(),
+            tail: ['\0', '\0'],
+            space: false,
+            result: Ok(()),
         }
     }
 
-    pub fn indent(&mut self) {
+    fn push_str(&mut self, mut s: &str) {
+        if self.result.is_err() || s.is_empty() {
+            return;
+        }
+        let mut space = false;
+        if self.style.minify {
+            self.flush_space();
+            if let Some(rest) = s.strip_suffix(' ') {
+                s = rest;
+                space = true;
+            }
+        }
+        let mut chars = s.chars();
+        if let Some(last) = chars.next_back() {
+            let prev = chars.next_back().unwrap_or(self.tail[1]);
+            self.tail = [prev, last];
+            if self.result.is_ok() {
+                self.result = self.out.write_str(s);
+            }
+        }
+        self.space = space;
+    }
+
+    fn push(&mut self, c: char) {
+        if self.result.is_err() {
+            return;
+        }
+        if self.style.minify {
+            return self.push_str(c.encode_utf8(&mut [0; 4]));
+        }
+        self.tail = [self.tail[1], c];
+        self.result = self.out.write_char(c);
+    }
+
+    /// Writes the space deferred when minifying
+    ///
+    /// Trailing space is deferred so that `start_block` can drop it.
+    fn flush_space(&mut self) {
+        if mem::replace(&mut self.space, false) {
+            self.tail = [self.tail[1], ' '];
+            self.result = self.out.write_char(' ');
+        }
+    }
+
+    pub fn sort_directives(&self) -> bool {
+        self.style.sort_directives
+    }
+
+    pub fn minify(&self) -> bool {
+        self.style.minify
+    }
+
+    /// Comments to write on separate lines at the next `indent()`
+    ///
+    /// This is how leading comments end up after the margin of a block.
+    pub fn leading_comments(&mut self, comments: &[String]) {
+        self.comments.extend(comments.iter().cloned());
+    }
+
+    /// Comment to write at the end of the current directive
+    ///
+    /// It's written either after `;` or after the closing `}`, whichever
+    /// is written at the current indentation level first. Returns a token
+    /// for `forget_trailing_comment`.
+    pub fn trailing_comment(&mut self, comment: &str) -> usize {
+        self.trailing.push((self.indent, comment.to_string()));
+        self.trailing.len() - 1
+    }
+
+    /// Drops trailing comment if it wasn't written yet
+    pub fn forget_trailing_comment(&mut self, token: usize) {
+        self.trailing.truncate(token);
+    }
+
+    fn line_end(&mut self) {
+        if self.style.minify {
+            return;
+        }
+        if self.trailing.last().map(|&(i, _)| i) == Some(self.indent) {
+            let (_, comment) = self.trailing.pop().unwrap();
+            self.push_str(" #");
+            self.push_str(&comment);
+        }
+        self.push('\n');
+    }
+
+    fn indent_line(&mut self) {
+        if self.style.minify {
+            return;
+        }
         for _ in 0..self.indent {
-            self.buf.push(' ');
+            self.push(' ');
         }
     }
 
+    pub fn indent(&mut self) {
+        let comments = mem::take(&mut self.comments);
+        if self.style.minify {
+            return;
+        }
+        for comment in comments {
+            self.indent_line();
+            self.push('#');
+            self.push_str(&comment);
+            self.push('\n');
+        }
+        self.indent_line();
+    }
+
     pub fn end(&mut self) {
-        self.buf.push(';');
-        self.buf.push('\n');
+        self.push(';');
+        self.line_end();
     }
     pub fn endline(&mut self) {
-        self.buf.push('\n');
+        if !self.style.minify {
+            self.push('\n');
+        }
     }
 
     pub fn start_block(&mut self) {
-        self.buf.push('{');
+        // `${` would start a variable name
+        if self.tail[1] != '$' {
+            self.space = false;
+        }
+        self.push('{');
         self.endline();
         self.indent += self.style.indent;
     }
@@ -70,26 +225,38 @@ impl<'a> Formatter<'a> {
     pub fn end_block(&mut self) {
         self.indent = self.indent.checked_sub(self.style.indent)
             .expect("negative indent");
-        self.indent();
-        self.buf.push('}');
-        self.endline();
+        self.indent_line();
+        self.push('}');
+        self.line_end();
     }
 
     pub fn margin(&mut self) {
-        if !self.buf.is_empty() && !self.buf.ends_with("{\n") {
-            self.buf.push('\n');
+        if self.style.minify {
+            return;
+        }
+        if self.tail[1] != '\0' && self.tail != ['{', '\n'] {
+            self.push('\n');
         }
     }
 
     pub fn write(&mut self, s: &str) {
-        self.buf.push_str(s);
+        self.push_str(s);
     }
     pub fn fmt<D: fmt::Display>(&mut self, s: &D) {
         use std::fmt::Write;
-        write!(&mut self.buf, "{}", s).expect("write never fails");
+        // errors are stored in `self.result` by `write_str`
+        write!(self, "{}", s).ok();
     }
 
-    pub fn into_string(self) -> String {
-        self.buf
+    /// Returns the first error of the underlying writer, if any
+    pub fn finish(self) -> fmt::Result {
+        self.result
+    }
+}
+
+impl<'a> fmt::Write for Formatter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        self.result
     }
 }
@@ -0,0 +1,19 @@
+use combine::{Parser};
+use combine::{choice};
+
+use ast::{Item};
+use grammar::value;
+use helpers::{semi, ident};
+use tokenizer::TokenStream;
+
+
+pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
+    choice((
+        ident("geoip_country").with(value()).skip(semi())
+            .map(Item::GeoipCountry),
+        ident("geoip_city").with(value()).skip(semi())
+            .map(Item::GeoipCity),
+        ident("geoip_org").with(value()).skip(semi())
+            .map(Item::GeoipOrg),
+    ))
+}
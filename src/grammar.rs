@@ -1,24 +1,35 @@
-use combine::{eof, many, many1, Parser};
-use combine::{choice, position};
+use std::str::FromStr;
+use std::time::Duration;
+
+use combine::{eof, many, many1, parser, Parser, Positioned, StreamOnce};
+use combine::{choice, optional, position};
 use combine::combinator::{opaque, no_partial, FnOpaque};
-use combine::error::StreamError;
-use combine::easy::Error;
+use combine::{ConsumedResult};
+use combine::error::{StreamError, Consumed, FastResult, Tracked};
+use combine::easy::{Error, Errors, Info};
+use combine::stream::Resetable;
 
-use ast::{self, Main, Directive, Item};
+use ast::{self, Main, Directive, Item, NameValue};
 use error::ParseError;
 use helpers::{semi, ident, text, string};
 use position::Pos;
 use tokenizer::{TokenStream, Token};
-use value::Value;
+use value::{Value, Interning};
 
 use access;
+use auth_basic;
+use charset;
 use core;
+use fastcgi;
+use geoip;
 use gzip;
 use headers;
 use proxy;
 use rewrite;
 use log;
 use real_ip;
+use ssl;
+use upstream;
 
 
 pub enum Code {
@@ -38,6 +49,89 @@ pub fn value<'a>() -> impl Parser<Output=Value, Input=TokenStream<'a>> {
     .and_then(|(p, v)| Value::parse(p, v))
 }
 
+pub fn name_value<'a>()
+    -> impl Parser<Output=NameValue, Input=TokenStream<'a>>
+{
+    value().and(value()).map(|(name, value)| NameValue { name, value })
+}
+
+/// Time intervals in descending order, in milliseconds
+const TIME_UNITS: &[(&str, u64)] = &[
+    ("y", 365*86_400_000),
+    ("M", 30*86_400_000),
+    ("w", 7*86_400_000),
+    ("d", 86_400_000),
+    ("h", 3_600_000),
+    ("m", 60_000),
+    ("s", 1000),
+    ("ms", 1),
+];
+
+pub(crate) fn parse_duration<'a>(s: &str)
+    -> Result<Duration, Error<Token<'a>, Token<'a>>>
+{
+    let invalid = || Error::unexpected_message(
+        format!("invalid time interval {:?}", s));
+    let mut rest = s;
+    let mut total = 0u64;
+    let mut prev_unit = None;
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(invalid());
+        }
+        let num: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit_len = if rest.starts_with("ms") {
+            2
+        } else {
+            rest.chars().next().map(|c| c.len_utf8()).unwrap_or(0)
+        };
+        // number without a unit means seconds
+        let unit = if rest.is_empty() { "s" } else { &rest[..unit_len] };
+        let idx = TIME_UNITS.iter().position(|&(u, _)| u == unit)
+            .ok_or_else(invalid)?;
+        if prev_unit.map(|p| p >= idx).unwrap_or(false) {
+            return Err(invalid());
+        }
+        prev_unit = Some(idx);
+        total = num.checked_mul(TIME_UNITS[idx].1)
+            .and_then(|n| total.checked_add(n))
+            .ok_or_else(invalid)?;
+        rest = &rest[unit_len..];
+    }
+    Ok(Duration::from_millis(total))
+}
+
+/// Parses size in bytes, e.g. `512`, `16k` or `10m`
+pub(crate) fn parse_size<'a>(s: &str)
+    -> Result<u64, Error<Token<'a>, Token<'a>>>
+{
+    let invalid = || Error::unexpected_message(
+        format!("invalid size {:?}", s));
+    let (num, scale) = match s.chars().last() {
+        Some('k') | Some('K') => (&s[..s.len()-1], 1 << 10),
+        Some('m') | Some('M') => (&s[..s.len()-1], 1 << 20),
+        Some('g') | Some('G') => (&s[..s.len()-1], 1 << 30),
+        _ => (s, 1),
+    };
+    if num.is_empty() || !num.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    num.parse::<u64>().ok()
+        .and_then(|n| n.checked_mul(scale))
+        .ok_or_else(invalid)
+}
+
+/// Time interval, e.g. `1h30m` or `100ms`
+pub fn duration<'a>() -> impl Parser<Output=Duration, Input=TokenStream<'a>> {
+    string().and_then(|t| parse_duration(t.value))
+}
+
 pub fn worker_processes<'a>()
     -> impl Parser<Output=Item, Input=TokenStream<'a>>
 {
@@ -75,9 +169,10 @@ pub fn server_name<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
 
 
 pub fn map<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
-    use tokenizer::Kind::{BlockStart, BlockEnd};
+    use tokenizer::Kind::{BlockStart, BlockEnd, Comment};
     use helpers::kind;
     enum Tok {
+        Comment,
         Hostnames,
         Volatile,
         Pattern(String, Value),
@@ -100,12 +195,15 @@ pub fn map<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
     }))
     .skip(kind(BlockStart))
     .and(many(choice((
-        ident("hostnames").map(|_| Tok::Hostnames),
-        ident("volatile").map(|_| Tok::Volatile),
-        ident("default").with(value()).map(|v| Tok::Default(v)),
-        ident("include").with(raw()).map(|v| Tok::Include(v)),
-        raw().and(value()).map(|(s, v)| Tok::Pattern(s, v)),
-    )).skip(semi())))
+        kind(Comment).map(|_| Tok::Comment),
+        choice((
+            ident("hostnames").map(|_| Tok::Hostnames),
+            ident("volatile").map(|_| Tok::Volatile),
+            ident("default").with(value()).map(|v| Tok::Default(v)),
+            ident("include").with(raw()).map(|v| Tok::Include(v)),
+            raw().and(value()).map(|(s, v)| Tok::Pattern(s, v)),
+        )).skip(semi()),
+    ))))
     .skip(kind(BlockEnd))
     .map(|((expression, variable), vec): ((_, _), Vec<Tok>)| {
         let mut res = ::ast::Map {
@@ -118,17 +216,21 @@ pub fn map<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
         };
         for val in vec {
             match val {
+                Tok::Comment => {}
                 Tok::Hostnames => res.hostnames = true,
                 Tok::Volatile => res.volatile = true,
                 Tok::Default(v) => res.default = Some(v),
                 Tok::Include(path) => res.includes.push(path),
                 Tok::Pattern(x, targ) => {
                     use ast::MapPattern::*;
-                    let mut s = &x[..];
-                    if s.starts_with('~') {
-                        res.patterns.push((Regex(s[1..].to_string()), targ));
+                    // only regexes are unquoted, exact keys are kept as
+                    // written so that `""` and `"a b"` are printed back
+                    if let Some(re) = unquote(&x).strip_prefix('~') {
+                        res.patterns.push((Regex(re.to_string()), targ));
                         continue;
-                    } else if s.starts_with('\\') {
+                    }
+                    let mut s = &x[..];
+                    if s.starts_with('\\') {
                         s = &s[1..];
                     }
                     let pat = if res.hostnames {
@@ -161,11 +263,11 @@ pub fn block<'a>()
         f(&mut no_partial((
                 position(),
                 kind(BlockStart)
-                    .with(many(directive()))
+                    .with(directives())
                     .skip(kind(BlockEnd)),
                 position(),
         ))
-        .map(|(s, dirs, e)| ((s, e), dirs)))
+        .map(|(s, dirs, e)| ((s, e), attach_comments(dirs))))
     })
 }
 
@@ -176,18 +278,38 @@ pub fn raw<'a>() -> impl Parser<Output=String, Input=TokenStream<'a>> {
     string().and_then(|t| Ok::<_, Error<_, _>>(t.value.to_string()))
 }
 
+/// A regular expression argument
+///
+/// Unlike `value()` no variables are scanned, as `$` in a regex is an
+/// anchor or a capture reference. Surrounding quotes are stripped, the rest
+/// is kept byte-for-byte.
+pub fn regex<'a>() -> impl Parser<Output=String, Input=TokenStream<'a>> {
+    string().map(|t| unquote(t.value).to_string())
+}
+
+/// Strips surrounding quotes, if any, leaving escapes intact
+pub(crate) fn unquote(s: &str) -> &str {
+    let quoted = s.len() >= 2 &&
+        (s.starts_with('"') && s.ends_with('"') ||
+         s.starts_with('\'') && s.ends_with('\''));
+    if quoted {
+        &s[1..s.len()-1]
+    } else {
+        s
+    }
+}
+
 pub fn location<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
     use ast::LocationPattern::*;
     ident("location").with(choice((
         text("=").with(raw().map(Exact)),
         text("^~").with(raw().map(FinalPrefix)),
-        text("~").with(raw().map(Regex)),
-        text("~*").with(raw().map(RegexInsensitive)),
+        text("~").with(regex().map(Regex)),
+        text("~*").with(regex().map(RegexInsensitive)),
         raw()
-            .map(|v| if v.starts_with('*') {
-                Named(v)
-            } else {
-                Prefix(v)
+            .map(|v| match v.strip_prefix('@') {
+                Some(name) => Named(name.to_string()),
+                None => Prefix(v),
             }),
     ))).and(block())
     .map(|(pattern, (position, directives))| {
@@ -271,6 +393,111 @@ pub fn openresty<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
     ))
 }
 
+/// Succeeds without consuming anything unless parsing in strict mode
+pub(crate) fn lenient<'a>()
+    -> impl Parser<Output=(), Input=TokenStream<'a>>
+{
+    parser(|input: &mut TokenStream<'a>| {
+        if input.strict() {
+            Err(Consumed::Empty(Errors::empty(input.position()).into()))
+        } else {
+            Ok(((), Consumed::Empty(())))
+        }
+    })
+}
+
+/// Returns true for a name followed by arguments and `;`, `{` or `}`
+///
+/// This is only a lexical check, nothing is parsed.
+fn looks_like_directive(text: &str) -> bool {
+    let text = text.trim();
+    let name_end = text.find(|c: char| {
+        !(c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    }).unwrap_or(text.len());
+    let (name, rest) = text.split_at(name_end);
+    name.starts_with(|c: char| c.is_ascii_lowercase()) &&
+        rest.starts_with([' ', '\t', ';', '{']) &&
+        rest.ends_with([';', '{', '}'])
+}
+
+/// A comment between directives, only emitted by tokenizer in lenient mode
+pub fn comment<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
+    use tokenizer::Kind::Comment;
+    use helpers::kind;
+    kind(Comment).map(|t| {
+        let text = &t.value[1..];
+        Item::Comment(ast::Comment {
+            text: text.to_string(),
+            looks_like_directive: looks_like_directive(text),
+        })
+    })
+}
+
+/// A comment on the same line, right after the directive
+///
+/// Only emitted by tokenizer in lenient mode, like other comments.
+fn trailing_comment<'a>()
+    -> impl Parser<Output=String, Input=TokenStream<'a>>
+{
+    use tokenizer::Kind::Comment;
+    use helpers::kind;
+    parser(|input: &mut TokenStream<'a>| {
+        if input.at_line_start() {
+            Err(Consumed::Empty(Errors::empty(input.position()).into()))
+        } else {
+            Ok(((), Consumed::Empty(())))
+        }
+    })
+    .with(kind(Comment))
+    .map(|t| t.value[1..].to_string())
+}
+
+/// A comment or a generic directive in a block having its own syntax
+///
+/// Like `generic()` and `comment()`, this only matches in lenient mode.
+pub(crate) fn unknown_directive<'a>()
+    -> impl Parser<Output=Directive, Input=TokenStream<'a>>
+{
+    position()
+    .and(choice((generic(), comment())))
+    .and(optional(trailing_comment()))
+    .map(|((position, item), trailing_comment)| Directive {
+        position,
+        item,
+        leading_comments: Vec::new(),
+        trailing_comment,
+    })
+}
+
+/// An argument of a generic directive
+///
+/// Arguments starting with `~` are regexes, like in `proxy_cookie_path`,
+/// so no variables are scanned in them.
+fn generic_arg<'a>() -> impl Parser<Output=Value, Input=TokenStream<'a>> {
+    (position(), string())
+    .and_then(|(p, t)| if unquote(t.value).starts_with('~') {
+        Ok(Value::regex(p, t.value))
+    } else {
+        Value::parse(p, t)
+    })
+}
+
+/// Any directive not known to the parser, only allowed in lenient mode
+pub fn generic<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
+    lenient()
+    .with(string())
+    .and(many(generic_arg()))
+    .and(choice((
+        semi().map(|_| None),
+        block().map(|(_, directives)| Some(directives)),
+    )))
+    .map(|((name, args), block)| Item::Generic(ast::Generic {
+        name: name.value.to_string(),
+        args,
+        block,
+    }))
+}
+
 pub fn directive<'a>() -> impl Parser<Output=Directive, Input=TokenStream<'a>>
 {
     position()
@@ -283,6 +510,9 @@ pub fn directive<'a>() -> impl Parser<Output=Directive, Input=TokenStream<'a>>
         ident("http").with(block())
             .map(|(position, directives)| ast::Http { position, directives })
             .map(Item::Http),
+        ident("stream").with(block())
+            .map(|(position, directives)| ast::Stream { position, directives })
+            .map(Item::Stream),
         ident("server").with(block())
             .map(|(position, directives)| ast::Server { position, directives })
             .map(Item::Server),
@@ -302,21 +532,214 @@ pub fn directive<'a>() -> impl Parser<Output=Directive, Input=TokenStream<'a>>
         proxy::directives(),
         gzip::directives(),
         core::directives(),
-        access::directives(),
-        log::directives(),
-        real_ip::directives(),
+        // nested as there is a limit on number of choices
+        choice((
+            access::directives(),
+            log::directives(),
+            real_ip::directives(),
+            charset::directives(),
+            auth_basic::directives(),
+            upstream::directives(),
+            fastcgi::directives(),
+            ssl::directives(),
+            geoip::directives(),
+        )),
         openresty(),
         // it's own module
         ident("empty_gif").skip(semi()).map(|_| Item::EmptyGif),
         ident("index").with(many(value())).skip(semi())
             .map(Item::Index),
+        choice((generic(), comment())),
     )))
-    .map(|(pos, dir)| Directive {
+    .and(optional(trailing_comment()))
+    .map(|((pos, dir), trailing_comment)| Directive {
         position: pos,
         item: dir,
+        leading_comments: Vec::new(),
+        trailing_comment,
     })
 }
 
+/// Returns true at the `}` or at the end of input, without consuming it
+fn at_block_end(input: &mut TokenStream) -> bool {
+    use tokenizer::Kind::BlockEnd;
+    let checkpoint = input.checkpoint();
+    let result = match input.uncons() {
+        Ok(tok) => tok.kind == BlockEnd,
+        Err(e) => e == Error::end_of_input(),
+    };
+    input.reset(checkpoint);
+    result
+}
+
+/// Skips a directive up to and including `;` or its block
+///
+/// Stops before the `}` closing the enclosing block. If the tokenizer can't
+/// proceed the rest of input is skipped.
+fn skip_directive(input: &mut TokenStream) {
+    use tokenizer::Kind::{Semicolon, BlockStart, BlockEnd};
+    let mut depth = 0;
+    loop {
+        let checkpoint = input.checkpoint();
+        match input.uncons() {
+            Ok(tok) => match tok.kind {
+                Semicolon if depth == 0 => break,
+                BlockStart => depth += 1,
+                BlockEnd if depth == 0 => {
+                    input.reset(checkpoint);
+                    break;
+                }
+                BlockEnd => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            },
+            Err(e) => {
+                if e != Error::end_of_input() {
+                    input.skip_to_end();
+                }
+                break;
+            }
+        }
+    }
+}
+
+/// Directives of a block or of the whole file, up to `}` or end of input
+///
+/// On a recovering stream a directive which fails to parse is recorded as
+/// an error and skipped.
+struct Directives<P> {
+    many: P,
+}
+
+fn directives<'a>()
+    -> impl Parser<Output=Vec<Directive>, Input=TokenStream<'a>>
+{
+    Directives { many: many(directive()) }
+}
+
+impl<'a, P> Parser for Directives<P>
+    where P: Parser<Output=Vec<Directive>, Input=TokenStream<'a>>
+{
+    type Input = TokenStream<'a>;
+    type Output = Vec<Directive>;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input)
+        -> ConsumedResult<Self::Output, Self::Input>
+    {
+        if !input.recovering() {
+            return self.many.parse_lazy(input);
+        }
+        let mut result = Vec::new();
+        let mut consumed = false;
+        while !at_block_end(input) {
+            let checkpoint = input.checkpoint();
+            match directive().parse_stream(input) {
+                Ok((dir, _)) => result.push(dir),
+                Err(e) => {
+                    input.reset(checkpoint);
+                    input.add_error(e.into_inner().error);
+                    skip_directive(input);
+                }
+            }
+            consumed = true;
+        }
+        if consumed {
+            FastResult::ConsumedOk(result)
+        } else {
+            FastResult::EmptyOk(result)
+        }
+    }
+
+    fn add_error(&mut self,
+        error: &mut Tracked<<Self::Input as StreamOnce>::Error>)
+    {
+        self.many.add_error(error)
+    }
+
+    fn add_consumed_expected_error(&mut self,
+        error: &mut Tracked<<Self::Input as StreamOnce>::Error>)
+    {
+        self.many.add_consumed_expected_error(error)
+    }
+}
+
+/// Moves comments written just above a directive into its leading comments
+///
+/// Comments must be on adjacent lines with no empty line between the last
+/// one and the directive. Commented out directives and comments on the same
+/// line as the previous directive are kept as `Item::Comment`.
+pub(crate) fn attach_comments(dirs: Vec<Directive>) -> Vec<Directive> {
+    let mut result = Vec::with_capacity(dirs.len());
+    let mut pending: Vec<Directive> = Vec::new();
+    let mut prev_line = None;
+    for mut dir in dirs {
+        let line = dir.position.line;
+        if let Some(last) = pending.last().map(|c| c.position.line) {
+            if last + 1 != line {
+                result.append(&mut pending);
+            }
+        }
+        match dir.item {
+            Item::Comment(ref c)
+                if !c.looks_like_directive && prev_line != Some(line)
+            => {
+                pending.push(dir);
+                continue;
+            }
+            Item::Comment(..) => {
+                result.append(&mut pending);
+            }
+            _ => {
+                if !pending.is_empty() {
+                    dir.leading_comments = pending.drain(..)
+                        .filter_map(|c| match c.item {
+                            Item::Comment(c) => Some(c.text),
+                            _ => None,
+                        })
+                        .collect();
+                }
+                prev_line = Some(line);
+            }
+        }
+        result.push(dir);
+    }
+    result.append(&mut pending);
+    result
+}
+
+
+/// Options of the parser
+///
+/// By default parser is lenient: directives it doesn't know are accepted
+/// as [`Item::Generic`](ast/enum.Item.html). Note that [`parse_main`] and
+/// [`parse_directives`] are always strict.
+///
+/// Lenient parser also keeps comments, except inside `map` and `types`
+/// blocks. Entries of these blocks have no place for them, so such
+/// comments are dropped, including ones on the same line as an entry.
+/// Inside `upstream` they are kept, but are printed after the known
+/// directives, see [`Upstream::directives`].
+///
+/// [`parse_main`]: fn.parse_main.html
+/// [`parse_directives`]: fn.parse_directives.html
+/// [`Upstream::directives`]: ast/struct.Upstream.html#structfield.directives
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ParseOptions {
+    strict: bool,
+}
+
+impl ParseOptions {
+    /// Reject directives unknown to the parser with an error
+    pub fn strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+}
 
 /// Parses a piece of config in "main" context (i.e. top-level)
 ///
@@ -327,14 +750,87 @@ pub fn parse_main(s: &str) -> Result<Main, ParseError> {
     parse_directives(s).map(|directives| Main { directives })
 }
 
+/// Parses a piece of config in "main" context with specified options
+///
+/// ```rust
+/// # use nginx_config::{parse_main_with, ParseOptions};
+/// let text = "more_set_headers \"Server: x\";";
+/// assert!(parse_main_with(text, &ParseOptions::default()).is_ok());
+/// assert!(parse_main_with(text, ParseOptions::default().strict(true))
+///     .is_err());
+/// ```
+pub fn parse_main_with(s: &str, options: &ParseOptions)
+    -> Result<Main, ParseError>
+{
+    parse_tokens(TokenStream::with_strict(s, options.strict))
+        .map(|directives| Main { directives })
+}
+
+impl FromStr for Main {
+    type Err = ParseError;
+    /// Parses config in "main" context, same as [`parse_main`]
+    ///
+    /// ```rust
+    /// # use nginx_config::ast::Main;
+    /// let config: Main = "worker_processes auto;".parse().unwrap();
+    /// assert_eq!(config.to_string(), "worker_processes auto;\n");
+    /// ```
+    ///
+    /// [`parse_main`]: fn.parse_main.html
+    fn from_str(s: &str) -> Result<Main, ParseError> {
+        parse_main(s)
+    }
+}
+
 /// Parses a piece of config from arbitrary context
 ///
 /// This implies no validation of what context directives belong to.
 pub fn parse_directives(s: &str) -> Result<Vec<Directive>, ParseError> {
-    let mut tokens = TokenStream::new(s);
+    parse_tokens(TokenStream::new(s))
+}
+
+/// Parses config in "main" context, collecting errors instead of failing
+///
+/// Directive which fails to parse is skipped up to the next `;`, or up to
+/// the end of its block, and parsing continues. This is useful to report
+/// all errors at once, for example in an editor. Like [`parse_main`] this
+/// is strict.
+///
+/// ```rust
+/// # use nginx_config::parse_recover;
+/// let (config, errors) = parse_recover("daemon yes; worker_processes 2;");
+/// assert_eq!(config.to_string(), "worker_processes 2;\n");
+/// assert_eq!(errors.len(), 1);
+/// ```
+///
+/// [`parse_main`]: fn.parse_main.html
+pub fn parse_recover(s: &str) -> (Main, Vec<ParseError>) {
+    let _names = Interning::start();
+    let mut tokens = TokenStream::with_recovery(s);
+    let mut result = Vec::new();
+    loop {
+        if let Ok((dirs, _)) = directives().parse_stream(&mut tokens) {
+            result.extend(dirs);
+        }
+        // only a stray `}` or the end of input stops directives here
+        let position = tokens.position();
+        match tokens.uncons() {
+            Ok(tok) => tokens.add_error(Errors::new(position,
+                Error::Unexpected(Info::Token(tok)))),
+            Err(_) => break,
+        }
+    }
+    let errors = tokens.take_errors().into_iter()
+        .map(ParseError::from).collect();
+    (Main { directives: attach_comments(result) }, errors)
+}
+
+fn parse_tokens(mut tokens: TokenStream) -> Result<Vec<Directive>, ParseError>
+{
+    let _names = Interning::start();
     let (doc, _) = many1(directive())
         .skip(eof())
         .parse_stream(&mut tokens)
         .map_err(|e| e.into_inner().error)?;
-    Ok(doc)
+    Ok(attach_comments(doc))
 }
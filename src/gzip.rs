@@ -1,9 +1,11 @@
 use combine::{many1, Parser};
 use combine::{choice};
+use combine::easy::Error;
+use combine::error::StreamError;
 
 use ast::{Item};
-use grammar::bool;
-use helpers::{semi, ident};
+use grammar::{bool, parse_size};
+use helpers::{semi, ident, string};
 use tokenizer::TokenStream;
 
 pub fn gzip_static<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
@@ -34,11 +36,34 @@ pub fn gzip_proxied<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
     .skip(semi())
 }
 
+pub fn gzip_comp_level<'a>()
+    -> impl Parser<Output=Item, Input=TokenStream<'a>>
+{
+    ident("gzip_comp_level").with(string().and_then(|t| {
+        match t.value.parse() {
+            Ok(level @ 1..=9) => Ok(level),
+            _ => Err(Error::unexpected_message(
+                "gzip_comp_level must be from 1 to 9")),
+        }
+    }))
+    .map(Item::GzipCompLevel)
+    .skip(semi())
+}
+
 pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
     choice((
         ident("gzip").with(bool()).skip(semi())
             .map(Item::Gzip),
         gzip_static(),
         gzip_proxied(),
+        gzip_comp_level(),
+        ident("gzip_types")
+            .with(many1(string().map(|t| t.value.to_string())))
+            .skip(semi())
+            .map(Item::GzipTypes),
+        ident("gzip_min_length")
+            .with(string().and_then(|t| parse_size(t.value)))
+            .skip(semi())
+            .map(Item::GzipMinLength),
     ))
 }
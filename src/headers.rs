@@ -2,23 +2,21 @@ use combine::{Parser, optional};
 use combine::{choice};
 
 use ast::{self, Item};
-use grammar::{value};
+use grammar::{name_value, value};
 use helpers::{semi, ident};
 use tokenizer::{TokenStream};
 
-fn add_header<'a>()
-    -> impl Parser<Output=Item, Input=TokenStream<'a>>
+fn add_header<'a>(name: &'static str)
+    -> impl Parser<Output=ast::AddHeader, Input=TokenStream<'a>>
 {
-    ident("add_header")
+    ident(name)
     .with((
-        value(),
-        value(),
+        name_value(),
         optional(ident("always").map(|_| ())),
-    )).map(|(field, value, always)| {
-        ast::AddHeader { field, value, always: always.is_some() }
+    )).map(|(header, always)| {
+        ast::AddHeader { header, always: always.is_some() }
     })
     .skip(semi())
-    .map(Item::AddHeader)
 }
 
 fn expires<'a>()
@@ -36,7 +34,8 @@ pub fn directives<'a>()
     -> impl Parser<Output=Item, Input=TokenStream<'a>>
 {
     choice((
-        add_header(),
+        add_header("add_header").map(Item::AddHeader),
+        add_header("add_trailer").map(Item::AddTrailer),
         expires(),
     ))
 }
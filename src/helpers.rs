@@ -1,7 +1,8 @@
 use std::marker::PhantomData;
 
-use combine::{Parser, ConsumedResult, satisfy, StreamOnce};
-use combine::error::{Tracked};
+use combine::{Parser, ConsumedResult, satisfy, StreamOnce, Positioned};
+use combine::error::{Tracked, FastResult};
+use combine::stream::Resetable;
 use combine::stream::easy::{Error, Errors, Info};
 
 use tokenizer::{TokenStream, Kind, Token};
@@ -21,6 +22,11 @@ pub struct Value<'a> {
     phantom: PhantomData<&'a u8>,
 }
 
+#[derive(Debug, Clone)]
+pub struct Semi<'a> {
+    phantom: PhantomData<&'a u8>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Prefix<'a> {
     value: &'static str,
@@ -34,8 +40,15 @@ pub fn kind<'x>(kind: Kind) -> TokenMatch<'x> {
     }
 }
 
-pub fn semi<'x>() -> TokenMatch<'x> {
-    kind(Kind::Semicolon)
+/// Matches the semicolon terminating a directive
+///
+/// When the directive is followed by `}` or the end of input instead, the
+/// error is reported at that token and only mentions the missing `;`,
+/// rather than every option the directive could have had.
+pub fn semi<'x>() -> Semi<'x> {
+    Semi {
+        phantom: PhantomData,
+    }
 }
 
 pub fn ident<'x>(value: &'static str) -> Value<'x> {
@@ -85,6 +98,41 @@ impl<'a> Parser for TokenMatch<'a> {
     }
 }
 
+impl<'a> Parser for Semi<'a> {
+    type Input = TokenStream<'a>;
+    type Output = Token<'a>;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input)
+        -> ConsumedResult<Self::Output, Self::Input>
+    {
+        let position = input.position();
+        let checkpoint = input.checkpoint();
+        let unexpected = match input.uncons() {
+            Ok(tok) if tok.kind == Kind::Semicolon => {
+                return FastResult::ConsumedOk(tok);
+            }
+            Ok(tok) if tok.kind == Kind::BlockEnd => Error::Unexpected(
+                Info::Token(tok)),
+            Err(ref e) if *e == Error::end_of_input() => Error::end_of_input(),
+            _ => {
+                input.reset(checkpoint);
+                return FastResult::EmptyErr(Errors::empty(position).into());
+            }
+        };
+        FastResult::ConsumedErr(Errors {
+            position,
+            errors: vec![unexpected, Error::Expected(Info::Borrowed(";"))],
+        })
+    }
+
+    fn add_error(&mut self,
+        error: &mut Tracked<<Self::Input as StreamOnce>::Error>)
+    {
+        error.error.add_error(Error::Expected(Info::Borrowed(";")));
+    }
+}
+
 impl<'a> Parser for Value<'a> {
     type Input = TokenStream<'a>;
     type Output = Token<'a>;
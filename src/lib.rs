@@ -13,7 +13,7 @@
 
 extern crate combine;
 #[cfg(feature="fuzzy_errors")] extern crate strsim;
-#[macro_use] extern crate failure;
+#[cfg(feature="regex")] extern crate regex;
 #[macro_use] extern crate matches;
 #[cfg(test)] #[macro_use] extern crate pretty_assertions;
 
@@ -23,21 +23,33 @@ mod error;
 mod format;
 mod grammar;
 mod helpers;
+mod normalize;
 mod position;
-mod tokenizer;
+pub mod tokenizer;
+mod validate;
 mod value;
+mod variables;
+mod vhost;
 pub mod visitors;
 
 mod access;
+mod auth_basic;
+mod charset;
 mod core;
+mod fastcgi;
+mod geoip;
 mod gzip;
 mod headers;
 mod proxy;
 mod rewrite;
 mod log;
 mod real_ip;
+mod ssl;
+mod upstream;
 
-pub use grammar::{parse_main, parse_directives};
+pub use grammar::{parse_main, parse_main_with, parse_directives};
+pub use grammar::parse_recover;
+pub use grammar::ParseOptions;
 pub use format::Style;
 pub use position::Pos;
 pub use error::ParseError;
@@ -1,11 +1,11 @@
-use combine::{many, Parser};
+use combine::{many, many1, Parser};
 use combine::{choice, optional, position};
 use combine::error::StreamError;
 use combine::easy::Error;
 
 use ast::{self, Item};
 use grammar::{value};
-use helpers::{semi, ident, string};
+use helpers::{semi, ident, string, prefix};
 use tokenizer::{TokenStream};
 use value::Value;
 
@@ -69,10 +69,35 @@ fn access_log<'a>()
     .map(Item::AccessLog)
 }
 
+fn log_format<'a>()
+    -> impl Parser<Output=Item, Input=TokenStream<'a>>
+{
+    ident("log_format")
+    .with(string())
+    .and(optional(prefix("escape=")))
+    .and(many1(value()))
+    .skip(semi())
+    .map(|((name, escape), parts): (_, Vec<_>)| {
+        // normally there is a single value after joining, but nginx
+        // concatenates unquoted parts too
+        let mut parts = Value::join_quoted(parts).into_iter();
+        let mut template = parts.next().unwrap();
+        for part in parts {
+            template.append(part);
+        }
+        Item::LogFormat(ast::LogFormat {
+            name: name.value.to_string(),
+            escape: escape.map(|x| x.to_string()),
+            template,
+        })
+    })
+}
+
 pub fn directives<'a>()
     -> impl Parser<Output=Item, Input=TokenStream<'a>>
 {
     choice((
         access_log(),
+        log_format(),
     ))
 }
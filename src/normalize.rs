@@ -0,0 +1,51 @@
+//! Canonicalization of equivalent directives
+//!
+//! Only rewrites which don't change how nginx interprets the config are
+//! done here.
+use ast::{Main, Directive, Item, Address};
+use value::{self, Value};
+use visitors::visit_mutable;
+
+
+fn lowercase(val: &mut Value) {
+    for item in &mut val.data {
+        if let value::Item::Literal(ref mut s) = *item {
+            *s = s.to_lowercase();
+        }
+    }
+}
+
+fn normalize_directive(dir: &mut Directive) {
+    match dir.item {
+        Item::Listen(ref mut lst) => {
+            let port = match lst.address {
+                Address::StarPort(port) => port,
+                Address::Ip(addr)
+                    if addr.is_ipv4() && addr.ip().is_unspecified()
+                => addr.port(),
+                _ => return,
+            };
+            lst.address = Address::Port(port);
+        }
+        Item::ProxyHideHeader(ref mut val) => lowercase(val),
+        Item::ProxyPassHeader(ref mut val) => lowercase(val),
+        _ => {}
+    }
+}
+
+impl Main {
+    /// Rewrites directives into their canonical form
+    ///
+    /// Currently this does the following:
+    ///
+    /// * `listen *:80` and `listen 0.0.0.0:80` become `listen 80`
+    /// * header names in `proxy_hide_header` and `proxy_pass_header` are
+    ///   lowercased, since nginx compares them case-insensitively
+    ///
+    /// Duplicate flags on `listen`, like `ssl ssl`, are already merged by
+    /// the parser. Header names which are sent as written, like in
+    /// `add_header`, are kept intact.
+    pub fn normalize(&mut self) {
+        visit_mutable(&mut self.directives, normalize_directive);
+    }
+}
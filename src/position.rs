@@ -9,6 +9,17 @@ pub struct Pos {
     pub column: usize,
 }
 
+impl Pos {
+    /// One-based line number
+    pub fn line(&self) -> usize {
+        self.line
+    }
+    /// One-based column number
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
 impl fmt::Debug for Pos {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Pos({}:{})", self.line, self.column)
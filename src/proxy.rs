@@ -1,21 +1,46 @@
 use combine::{Parser};
-use combine::{choice, many1};
+use combine::{choice, many1, position};
 use combine::error::StreamError;
 use combine::easy::Error;
 
 use ast::{self, Item};
 use helpers::{semi, ident, string};
-use tokenizer::TokenStream;
-use grammar::{value, bool, Code};
+use position::Pos;
+use tokenizer::{TokenStream, Token};
+use grammar::{value, name_value, bool, duration, parse_duration, unquote};
+use grammar::Code;
+use value::Value;
 
 
+fn redirect_or_regex<'a>(pos: Pos, redirect: Token<'a>, replacement: Value)
+    -> Result<ast::ProxyRedirect, Error<Token<'a>, Token<'a>>>
+{
+    use ast::ProxyRedirect::*;
+    let text = unquote(redirect.value);
+    if let Some(pattern) = text.strip_prefix("~*") {
+        return Ok(Regex {
+            pattern: pattern.to_string(),
+            case_insensitive: true,
+            replacement,
+        });
+    } else if let Some(pattern) = text.strip_prefix('~') {
+        return Ok(Regex {
+            pattern: pattern.to_string(),
+            case_insensitive: false,
+            replacement,
+        });
+    }
+    let redirect = Value::parse(pos, redirect)?;
+    Ok(Redirect { redirect, replacement })
+}
+
 pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
     choice((
         ident("proxy_pass").with(value()).skip(semi())
-            .map(Item::ProxyPass),
-        ident("proxy_set_header").with(value()).and(value())
+            .map(|url| Item::ProxyPass(ast::ProxyPass { url })),
+        ident("proxy_set_header").with(name_value())
             .skip(semi())
-            .map(|(field, value)| Item::ProxySetHeader { field, value }),
+            .map(Item::ProxySetHeader),
         ident("proxy_method").with(value()).skip(semi())
             .map(Item::ProxyMethod),
         ident("proxy_cache").with(value()).skip(semi())
@@ -23,19 +48,18 @@ pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
         ident("proxy_cache_key").with(value()).skip(semi())
             .map(Item::ProxyCacheKey),
         ident("proxy_cache_valid")
-            .with(many1(value()))
+            .with(many1(string()))
             .and_then(|mut v: Vec<_>| {
                 use ast::ProxyCacheValid::*;
-                use value::Item::*;
-                let time = v.pop().unwrap();
+                let time = parse_duration(v.pop().unwrap().value)?;
                 if v.len() == 0 {
                     return Ok(Normal(time));
                 }
                 let mut codes = Vec::new();
                 let items = v.len();
                 for item in v {
-                    match &item.data[..] {
-                        [Literal(x)] if x == "any" => {
+                    match item.value {
+                        "any" => {
                             if items == 1 {
                                 return Ok(Any(time));
                             } else {
@@ -45,7 +69,7 @@ pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
                                      other codes"));
                             }
                         }
-                        [Literal(x)] => {
+                        x => {
                             match Code::parse(x) {
                                 Ok(code) => {
                                     codes.push(code.as_code())
@@ -56,19 +80,17 @@ pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
                                 }
                             }
                         }
-                        _ => {
-                            return Err(Error::unexpected_message(
-                                "variables aren't allowed in list of codes"));
-                        }
                     }
                 }
                 return Ok(Specific(codes, time));
             })
             .skip(semi()).map(Item::ProxyCacheValid),
-        ident("proxy_read_timeout").with(value()).skip(semi())
+        ident("proxy_read_timeout").with(duration()).skip(semi())
             .map(Item::ProxyReadTimeout),
-        ident("proxy_connect_timeout").with(value()).skip(semi())
+        ident("proxy_connect_timeout").with(duration()).skip(semi())
             .map(Item::ProxyConnectTimeout),
+        ident("proxy_send_timeout").with(duration()).skip(semi())
+            .map(Item::ProxySendTimeout),
         ident("proxy_hide_header").with(value()).skip(semi())
             .map(Item::ProxyHideHeader),
         ident("proxy_pass_header").with(value()).skip(semi())
@@ -123,5 +145,15 @@ pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
             .map(Item::ProxyNextUpstreamTries),
         ident("proxy_next_upstream_timeout").with(value()).skip(semi())
             .map(Item::ProxyNextUpstreamTimeout),
+        ident("proxy_redirect").with(choice((
+            ident("default").map(|_| ast::ProxyRedirect::Default),
+            ident("off").map(|_| ast::ProxyRedirect::Off),
+            (position(), string()).and(value())
+                .and_then(|((pos, redirect), replacement)| {
+                    redirect_or_regex(pos, redirect, replacement)
+                }),
+        )))
+            .skip(semi())
+            .map(Item::ProxyRedirect),
     ))
 }
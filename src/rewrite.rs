@@ -4,7 +4,7 @@ use combine::error::StreamError;
 use combine::easy::Error;
 
 use ast::{self, Item};
-use grammar::{value, block, Code};
+use grammar::{value, bool, block, regex, unquote, Code};
 use helpers::{semi, ident, string};
 use position::Pos;
 use tokenizer::{TokenStream, Token};
@@ -18,7 +18,7 @@ fn rewrite<'a>()
     use ast::Item::Rewrite;
 
     ident("rewrite")
-    .with(string())
+    .with(regex())
     .and(value())
     .and(optional(choice((
         ident("last").map(|_| Last),
@@ -28,7 +28,7 @@ fn rewrite<'a>()
     ))))
     .map(|((regex, replacement), flag)| {
         Rewrite(ast::Rewrite {
-            regex: regex.value.to_string(), replacement, flag,
+            regex, replacement, flag,
         })
     })
     .skip(semi())
@@ -92,7 +92,7 @@ fn return_directive<'a>()
                 Some(Literal(x))
                 if x.starts_with("https://") || x.starts_with("http://")
                 => Ok(Redirect { code: None, url: a.clone()}),
-                Some(Variable(v)) if v == "scheme"
+                Some(Variable(v)) if &v[..] == "scheme"
                 => Ok(Redirect { code: None, url: a.clone()}),
                 _ => {
                     match Code::parse(lit(&a)?)? {
@@ -157,23 +157,25 @@ fn parse_unary<'a>(mut v: Vec<&str>, position: Pos)
     -> Result<ast::IfCondition, Error<Token<'a>, Token<'a>>>
 {
     use ast::IfCondition::*;
+    use ast::FileTestKind;
 
     let oper = v.remove(0);
     let right = Value::parse_str(position, v.remove(0))?;
     if v.len() > 0 {
         return Err(Error::unexpected_message("extra argument to condition"));
     }
-    match oper {
-        "-d" => return Ok(DirExists(right)),
-        "!-d" => return Ok(DirNotExists(right)),
-        "-f" => return Ok(FileExists(right)),
-        "!-f" => return Ok(FileNotExists(right)),
-        "-x" => return Ok(Executable(right)),
-        "!-x" => return Ok(NotExecutable(right)),
-        "-e" => return Ok(Exists(right)),
-        "!-e" => return Ok(NotExists(right)),
+    let (negated, name) = match oper.strip_prefix('!') {
+        Some(name) => (true, name),
+        None => (false, oper),
+    };
+    let kind = match name {
+        "-d" => FileTestKind::Dir,
+        "-f" => FileTestKind::File,
+        "-x" => FileTestKind::Executable,
+        "-e" => FileTestKind::Exists,
         _ => return Err(Error::unexpected_message("missing parenthesis")),
-    }
+    };
+    Ok(FileTest { negated, kind, operand: right })
 }
 
 fn parse_binary<'a>(mut v: Vec<&str>, position: Pos)
@@ -187,17 +189,19 @@ fn parse_binary<'a>(mut v: Vec<&str>, position: Pos)
     }
     let oper = v.remove(0);
     let right = match &v[..] {
-        [x] => x.to_string(),
+        [x] => *x,
         _ => return Err(Error::unexpected_message(
                 "you can only compare against a single literal")),
     };
     match oper {
-        "=" => return Ok(Eq(left, right)),
-        "!=" => return Ok(Neq(left, right)),
-        "~" => return Ok(RegEq(left, right, true)),
-        "!~" => return Ok(RegNeq(left, right, true)),
-        "~*" => return Ok(RegEq(left, right, false)),
-        "!~*" => return Ok(RegNeq(left, right, false)),
+        "=" => return Ok(Eq(left, Value::parse_str(position, right)?)),
+        "!=" => return Ok(Neq(left, Value::parse_str(position, right)?)),
+        "~" | "!~" | "~*" | "!~*" => return Ok(Regex {
+            variable: left,
+            pattern: unquote(right).to_string(),
+            negated: oper.starts_with('!'),
+            case_insensitive: oper.ends_with('*'),
+        }),
         _ => return Err(Error::unexpected_message("missing parenthesis")),
     }
 }
@@ -239,5 +243,7 @@ pub fn directives<'a>()
         set(),
         return_directive(),
         if_directive(),
+        ident("rewrite_log").with(bool()).skip(semi())
+            .map(Item::RewriteLog),
     ))
 }
@@ -0,0 +1,62 @@
+use combine::{many1, Parser};
+use combine::choice;
+use combine::easy::Error;
+use combine::error::StreamError;
+
+use ast::{Item, SslSessionCache, SslSessionCacheKind};
+use grammar::{value, bool, parse_size};
+use helpers::{semi, ident, string};
+use tokenizer::{TokenStream, Token};
+
+
+fn cache_kind<'a>(val: &str)
+    -> Result<SslSessionCacheKind, Error<Token<'a>, Token<'a>>>
+{
+    use ast::SslSessionCacheKind::*;
+    if val == "builtin" {
+        return Ok(Builtin(None));
+    }
+    if let Some(size) = val.strip_prefix("builtin:") {
+        return Ok(Builtin(Some(size.parse()?)));
+    }
+    if let Some(rest) = val.strip_prefix("shared:") {
+        if let Some(colon) = rest.rfind(':') {
+            if colon > 0 {
+                return Ok(Shared {
+                    name: rest[..colon].to_string(),
+                    size: parse_size(&rest[colon+1..])?,
+                });
+            }
+        }
+        return Err(Error::unexpected_message(
+            "shared cache must be specified as shared:name:size"));
+    }
+    Err(Error::unexpected_message(
+        format!("invalid ssl_session_cache {:?}", val)))
+}
+
+fn ssl_session_cache<'a>()
+    -> impl Parser<Output=Item, Input=TokenStream<'a>>
+{
+    ident("ssl_session_cache")
+    .with(choice((
+        ident("off").map(|_| SslSessionCache::Off),
+        ident("none").map(|_| SslSessionCache::None),
+        many1(string().and_then(|t| cache_kind(t.value)))
+            .map(SslSessionCache::Caches),
+    )))
+    .skip(semi())
+    .map(Item::SslSessionCache)
+}
+
+pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
+    choice((
+        ssl_session_cache(),
+        ident("ssl_dhparam").with(value()).skip(semi())
+            .map(Item::SslDhparam),
+        ident("ssl_ecdh_curve").with(value()).skip(semi())
+            .map(Item::SslEcdhCurve),
+        ident("ssl_prefer_server_ciphers").with(bool()).skip(semi())
+            .map(Item::SslPreferServerCiphers),
+    ))
+}
@@ -1,3 +1,7 @@
+//! Token-level access to the config text
+//!
+//! This is useful for tools like syntax highlighters which don't need the
+//! whole AST.
 use std::fmt;
 
 use combine::{StreamOnce, Positioned};
@@ -5,35 +9,91 @@ use combine::error::{StreamError};
 use combine::stream::{Resetable};
 use combine::easy::{Error, Errors};
 
+use error::{ParseError, InternalError};
 use position::Pos;
 
 
+/// Type of the token
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Kind {
+    /// Any word: directive name or an argument, possibly quoted
     String,
+    /// The `;` sign
     Semicolon,
+    /// The `{` sign
     BlockStart,
+    /// The `}` sign
     BlockEnd,
+    /// A `#` comment up to the end of line, only emitted by [`tokens`]
+    ///
+    /// [`tokens`]: fn.tokens.html
+    Comment,
 }
 
+/// Classification of tokens useful for syntax highlighting
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TokenKind {
+    /// Directive name
+    Ident,
+    /// Unquoted argument
+    Value,
+    /// Unquoted argument starting with a variable
+    Variable,
+    /// Quoted argument
+    String,
+    /// The `;` sign
+    Semi,
+    /// The `{` sign
+    OpenBrace,
+    /// The `}` sign
+    CloseBrace,
+    /// A `#` comment
+    Comment,
+}
+
+/// A single token of the config
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Token<'a> {
+    /// Type of the token
     pub kind: Kind,
+    /// Original text of the token (including quotes, if any)
     pub value: &'a str,
 }
 
+/// An iterator over tokens returned by [`tokens`](fn.tokens.html)
+#[derive(Debug)]
+pub struct Tokens<'a> {
+    stream: TokenStream<'a>,
+    done: bool,
+}
+
+/// An iterator returned by [`token_kinds`](fn.token_kinds.html)
+#[derive(Debug)]
+pub struct TokenKinds<'a> {
+    tokens: Tokens<'a>,
+    directive_start: bool,
+}
+
+#[doc(hidden)]
 #[derive(Debug, PartialEq)]
 pub struct TokenStream<'a> {
     buf: &'a str,
     position: Pos,
     off: usize,
-    next_state: Option<(usize, Token<'a>, usize, Pos)>,
+    next_state: Option<(usize, Token<'a>, usize, Pos, bool)>,
+    /// Whether there is a line break between previous and next token
+    line_start: bool,
+    comments: bool,
+    strict: bool,
+    recovered: Option<Vec<InternalError<'a>>>,
 }
 
+#[doc(hidden)]
 #[derive(Clone, Debug, PartialEq)]
 pub struct Checkpoint {
     position: Pos,
     off: usize,
+    line_start: bool,
 }
 
 impl<'a> StreamOnce for TokenStream<'a> {
@@ -43,19 +103,22 @@ impl<'a> StreamOnce for TokenStream<'a> {
     type Error = Errors<Token<'a>, Token<'a>, Pos>;
 
     fn uncons(&mut self) -> Result<Self::Item, Error<Token<'a>, Token<'a>>> {
-        if let Some((at, tok, off, pos)) = self.next_state {
+        if let Some((at, tok, off, pos, line_start)) = self.next_state {
             if at == self.off {
                 self.off = off;
                 self.position = pos;
+                self.line_start = line_start;
                 return Ok(tok);
             }
         }
         let old_pos = self.off;
         let (kind, len) = self.peek_token()?;
         let value = &self.buf[self.off-len..self.off];
-        self.skip_whitespace();
+        self.skip_whitespace(matches!(kind,
+            Kind::Semicolon | Kind::BlockStart | Kind::BlockEnd | Kind::Comment));
         let token = Token { kind, value };
-        self.next_state = Some((old_pos, token, self.off, self.position));
+        self.next_state = Some((old_pos, token, self.off, self.position,
+                                self.line_start));
         Ok(token)
     }
 }
@@ -72,23 +135,82 @@ impl<'a> Resetable for TokenStream<'a> {
         Checkpoint {
             position: self.position,
             off: self.off,
+            line_start: self.line_start,
         }
     }
     fn reset(&mut self, checkpoint: Checkpoint) {
         self.position = checkpoint.position;
         self.off = checkpoint.off;
+        self.line_start = checkpoint.line_start;
     }
 }
 
 impl<'a> TokenStream<'a> {
     pub fn new(s: &str) -> TokenStream {
+        TokenStream::init(s, false, true)
+    }
+
+    /// Creates a stream for the parser
+    ///
+    /// When not `strict`, comments between directives are emitted as
+    /// `Kind::Comment` tokens, comments elsewhere are skipped.
+    pub(crate) fn with_strict(s: &str, strict: bool) -> TokenStream<'_> {
+        TokenStream::init(s, false, strict)
+    }
+
+    /// Whether directives unknown to the parser are rejected
+    pub(crate) fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Whether the next token is the first one on its line
+    pub(crate) fn at_line_start(&self) -> bool {
+        self.line_start
+    }
+
+    /// Creates a stream for the parser which collects errors
+    ///
+    /// Parser skips invalid directives and continues on such stream, errors
+    /// are returned by `take_errors`.
+    pub(crate) fn with_recovery(s: &str) -> TokenStream<'_> {
+        let mut me = TokenStream::init(s, false, true);
+        me.recovered = Some(Vec::new());
+        me
+    }
+
+    /// Whether parser should skip invalid directives
+    pub(crate) fn recovering(&self) -> bool {
+        self.recovered.is_some()
+    }
+
+    pub(crate) fn add_error(&mut self, error: InternalError<'a>) {
+        if let Some(ref mut errors) = self.recovered {
+            errors.push(error);
+        }
+    }
+
+    pub(crate) fn take_errors(&mut self) -> Vec<InternalError<'a>> {
+        self.recovered.take().unwrap_or_default()
+    }
+
+    /// Skips the rest of input, used to recover from tokenizer errors
+    pub(crate) fn skip_to_end(&mut self) {
+        self.off = self.buf.len();
+    }
+
+    fn init(s: &str, comments: bool, strict: bool) -> TokenStream<'_> {
         let mut me = TokenStream {
             buf: s,
             position: Pos { line: 1, column: 1 },
             off: 0,
             next_state: None,
+            line_start: true,
+            comments,
+            strict,
+            recovered: None,
         };
-        me.skip_whitespace();
+        me.skip_whitespace(true);
+        me.line_start = true;
         me
     }
 
@@ -103,6 +225,15 @@ impl<'a> TokenStream<'a> {
         };
 
         match cur_char {
+            // only reached if skip_whitespace stopped at the comment
+            '#' => {
+                let rest = &self.buf[self.off..];
+                let len = rest.find(&['\r', '\n'][..])
+                    .unwrap_or(rest.len());
+                self.position.column += rest[..len].chars().count();
+                self.off += len;
+                Ok((Comment, len))
+            }
             '{' => {
                 self.position.column += 1;
                 self.off += 1;
@@ -122,10 +253,15 @@ impl<'a> TokenStream<'a> {
                 let open_quote = cur_char;
                 let mut prev_char = cur_char;
                 let mut nchars = 1;
+                // offset of the `$` of the `${` not closed yet
+                let mut brace = None;
                 for (idx, cur_char) in iter {
                     nchars += 1;
                     match cur_char {
                         x if x == open_quote && prev_char != '\\' => {
+                            if let Some(offset) = brace {
+                                return Err(self.unclosed_brace(offset));
+                            }
                             self.position.column += nchars;
                             self.off += idx+1;
                             return Ok((String, idx+1));
@@ -137,6 +273,10 @@ impl<'a> TokenStream<'a> {
                                 )
                             );
                         }
+                        '{' if prev_char == '$' && brace.is_none() => {
+                            brace = Some(nchars - 2);
+                        }
+                        '}' => brace = None,
                         _ => {
 
                         }
@@ -156,13 +296,20 @@ impl<'a> TokenStream<'a> {
                             return Ok((String, nchars));
                         }
                         '{' if prev_char == '$' => {
-                            while let Some((_, cur_char)) = iter.next() {
-                                nchars += 1;
-                                if cur_char == '}' {
-                                    break;
+                            let offset = nchars - 1;
+                            loop {
+                                match iter.next() {
+                                    Some((_, cur_char)) => {
+                                        nchars += 1;
+                                        if cur_char == '}' {
+                                            break;
+                                        }
+                                    }
+                                    None => {
+                                        return Err(self.unclosed_brace(offset))
+                                    }
                                 }
                             }
-                            // TODO(tailhook) validate end of file
                         }
                         ';' | '{' | '}' | ' ' |
                         '\"' | '\'' => {
@@ -191,7 +338,20 @@ impl<'a> TokenStream<'a> {
         }
     }
 
-    fn skip_whitespace(&mut self) {
+    /// Error for `${` at `offset` chars from the start of current token
+    fn unclosed_brace(&mut self, offset: usize) -> Error<Token<'a>, Token<'a>>
+    {
+        self.position.column += offset;
+        Error::unexpected_message("unclosed variable brace")
+    }
+
+    /// Skips whitespace and comments
+    ///
+    /// Stops at a comment if comments are emitted as tokens: always in
+    /// `comments` mode, and only at directive boundaries in non-strict mode.
+    fn skip_whitespace(&mut self, boundary: bool) {
+        let line = self.position.line;
+        let stop_at_comment = self.comments || (boundary && !self.strict);
         let mut iter = self.buf[self.off..].char_indices();
         let idx = loop {
             let (idx, cur_char) = match iter.next() {
@@ -210,6 +370,7 @@ impl<'a> TokenStream<'a> {
                     continue;
                 }
                 //comment
+                '#' if stop_at_comment => break idx,
                 '#' => {
                     while let Some((_, cur_char)) = iter.next() {
                         if cur_char == '\r' || cur_char == '\n' {
@@ -224,6 +385,79 @@ impl<'a> TokenStream<'a> {
             }
         };
         self.off += idx;
+        self.line_start = self.position.line != line;
+    }
+}
+
+/// Returns an iterator over tokens of the config and their positions
+///
+/// Whitespace is skipped, comments are returned as `Kind::Comment` tokens.
+/// Iteration stops at the first error.
+pub fn tokens(input: &str) -> Tokens<'_> {
+    Tokens {
+        stream: TokenStream::init(input, true, true),
+        done: false,
+    }
+}
+
+/// Returns an iterator over tokens classified for syntax highlighting
+///
+/// This is the same as [`tokens`] but tells directive names apart from
+/// their arguments, and variables from other values.
+///
+/// [`tokens`]: fn.tokens.html
+pub fn token_kinds(input: &str) -> TokenKinds<'_> {
+    TokenKinds {
+        tokens: tokens(input),
+        directive_start: true,
+    }
+}
+
+impl<'a> Iterator for TokenKinds<'a> {
+    type Item = Result<(Pos, TokenKind, &'a str), ParseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (pos, tok) = match self.tokens.next()? {
+            Ok(pair) => pair,
+            Err(e) => return Some(Err(e)),
+        };
+        let kind = match tok.kind {
+            Kind::Comment => TokenKind::Comment,
+            Kind::Semicolon => TokenKind::Semi,
+            Kind::BlockStart => TokenKind::OpenBrace,
+            Kind::BlockEnd => TokenKind::CloseBrace,
+            Kind::String if self.directive_start => TokenKind::Ident,
+            Kind::String if tok.value.starts_with('"') ||
+                            tok.value.starts_with('\'') => TokenKind::String,
+            Kind::String if tok.value.starts_with('$') => TokenKind::Variable,
+            Kind::String => TokenKind::Value,
+        };
+        match tok.kind {
+            Kind::Comment => {},
+            Kind::String => self.directive_start = false,
+            _ => self.directive_start = true,
+        }
+        Some(Ok((pos, kind, tok.value)))
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<(Pos, Token<'a>), ParseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let pos = self.stream.position();
+        match self.stream.uncons() {
+            Ok(tok) => Some(Ok((pos, tok))),
+            Err(ref e) if e == &Error::end_of_input() => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(Errors::new(pos, e).into()))
+            }
+        }
     }
 }
 
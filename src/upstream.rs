@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use combine::{Parser};
+use combine::{choice, many, optional};
+
+use ast::{self, Directive, Item, BalancingMethod};
+use grammar::{value, duration, unknown_directive, attach_comments};
+use helpers::{semi, ident, prefix, string};
+use tokenizer::TokenStream;
+use value::Value;
+
+
+enum ServerParts {
+    Weight(u32),
+    Other(Value),
+}
+
+pub fn upstream<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
+    use tokenizer::Kind::{BlockStart, BlockEnd};
+    use helpers::kind;
+    enum Tok {
+        Balancing(BalancingMethod),
+        Server(ast::UpstreamServer),
+        Keepalive(u32),
+        KeepaliveRequests(u32),
+        KeepaliveTimeout(Duration),
+        Unknown(Directive),
+    }
+    ident("upstream")
+    .with(string())
+    .skip(kind(BlockStart))
+    .and(many(choice((
+        choice((
+            ident("least_conn").map(|_| BalancingMethod::LeastConn)
+                .map(Tok::Balancing),
+            ident("ip_hash").map(|_| BalancingMethod::IpHash)
+                .map(Tok::Balancing),
+            ident("hash").with(value())
+                .and(optional(ident("consistent")))
+                .map(|(key, consistent)| BalancingMethod::Hash {
+                    key,
+                    consistent: consistent.is_some(),
+                })
+                .map(Tok::Balancing),
+            ident("server").with(value())
+                .and(many::<Vec<_>, _>(choice((
+                    prefix("weight=").and_then(|v| v.parse())
+                        .map(ServerParts::Weight),
+                    value().map(ServerParts::Other),
+                ))))
+                .map(|(address, parts)| {
+                    let mut srv = ast::UpstreamServer {
+                        address,
+                        weight: None,
+                        params: Vec::new(),
+                    };
+                    for part in parts {
+                        match part {
+                            ServerParts::Weight(w) => srv.weight = Some(w),
+                            ServerParts::Other(v) => srv.params.push(v),
+                        }
+                    }
+                    srv
+                })
+                .map(Tok::Server),
+            ident("keepalive")
+                .with(string().and_then(|s| s.value.parse()))
+                .map(Tok::Keepalive),
+            ident("keepalive_requests")
+                .with(string().and_then(|s| s.value.parse()))
+                .map(Tok::KeepaliveRequests),
+            ident("keepalive_timeout").with(duration())
+                .map(Tok::KeepaliveTimeout),
+        )).skip(semi()),
+        unknown_directive().map(Tok::Unknown),
+    ))))
+    .skip(kind(BlockEnd))
+    .map(|(name, vec): (_, Vec<Tok>)| {
+        let mut res = ast::Upstream {
+            name: name.value.to_string(),
+            balancing: None,
+            servers: Vec::new(),
+            keepalive: None,
+            keepalive_requests: None,
+            keepalive_timeout: None,
+            directives: Vec::new(),
+        };
+        for val in vec {
+            match val {
+                Tok::Balancing(b) => res.balancing = Some(b),
+                Tok::Server(s) => res.servers.push(s),
+                Tok::Keepalive(n) => res.keepalive = Some(n),
+                Tok::KeepaliveRequests(n) => res.keepalive_requests = Some(n),
+                Tok::KeepaliveTimeout(t) => res.keepalive_timeout = Some(t),
+                Tok::Unknown(dir) => res.directives.push(dir),
+            }
+        }
+        res.directives = attach_comments(res.directives);
+        Item::Upstream(res)
+    })
+}
+
+pub fn directives<'a>() -> impl Parser<Output=Item, Input=TokenStream<'a>> {
+    upstream()
+}
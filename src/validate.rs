@@ -0,0 +1,345 @@
+//! Checks for common configuration mistakes
+//!
+//! These don't make config invalid syntactically, but nginx either refuses
+//! to start with them or behaves not as expected.
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+use ast::{Main, Directive, Item, LocationPattern, TryFilesLastOption};
+use ast::{ProxyPass, RewriteFlag, ServerTokens};
+use position::Pos;
+use value;
+use variables;
+use visitors::DirectiveIter;
+
+
+fn has_certificate(dirs: &[Directive]) -> bool {
+    dirs.iter().any(|d| matches!(d.item, Item::SslCertificate(..)))
+}
+
+fn safe_in_if(dir: &Directive) -> bool {
+    match dir.item {
+        Item::Return(..) => true,
+        Item::Rewrite(ref rw) => rw.flag == Some(RewriteFlag::Last),
+        Item::Comment(..) => true,
+        _ => false,
+    }
+}
+
+fn check_ssl(dirs: &[Directive], inherited: bool, result: &mut Vec<Pos>) {
+    let inherited = inherited || has_certificate(dirs);
+    for dir in dirs {
+        match dir.item {
+            Item::Http(ref h) => check_ssl(&h.directives, inherited, result),
+            Item::Server(ref s) => {
+                let ssl = s.directives.iter()
+                    .any(|d| matches!(d.item, Item::Listen(ref l) if l.ssl));
+                if ssl && !inherited && !has_certificate(&s.directives) {
+                    result.push(dir.position);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn has_resolver(dirs: &[Directive]) -> bool {
+    // `resolver` is only parsed as a generic directive
+    dirs.iter().any(|d| d.item.keyword() == "resolver")
+}
+
+/// Returns true if the url has variables and host needs to be resolved
+///
+/// Host is taken from the literal part of url up to the first variable.
+/// Addresses, unix sockets and names of upstreams are never resolved.
+fn needs_resolver(pass: &ProxyPass, upstreams: &HashSet<&str>) -> bool {
+    if pass.url.variables().next().is_none() {
+        return false;
+    }
+    let rest = match pass.url.data.first() {
+        Some(value::Item::Literal(s)) => match s.find("://") {
+            Some(idx) => &s[idx+3..],
+            None => return true,
+        },
+        _ => return true,
+    };
+    if rest.starts_with("unix:") || rest.starts_with('[') {
+        return false;
+    }
+    let host = rest.split(['/', ':']).next().unwrap_or("");
+    host.is_empty() ||
+        host.parse::<IpAddr>().is_err() && !upstreams.contains(host)
+}
+
+fn unresolved_proxy_pass(dirs: &[Directive], inherited: bool,
+    upstreams: &HashSet<&str>)
+    -> bool
+{
+    let inherited = inherited || has_resolver(dirs);
+    dirs.iter().any(|dir| match dir.item {
+        Item::ProxyPass(ref pass) => {
+            !inherited && needs_resolver(pass, upstreams)
+        }
+        ref item => item.children().is_some_and(|children| {
+            unresolved_proxy_pass(children, inherited, upstreams)
+        }),
+    })
+}
+
+fn check_resolver(dirs: &[Directive], inherited: bool,
+    upstreams: &HashSet<&str>, result: &mut Vec<Pos>)
+{
+    let inherited = inherited || has_resolver(dirs);
+    for dir in dirs {
+        match dir.item {
+            Item::Http(ref h) => {
+                check_resolver(&h.directives, inherited, upstreams, result)
+            }
+            Item::Stream(ref s) => {
+                check_resolver(&s.directives, inherited, upstreams, result)
+            }
+            Item::Server(ref s)
+                if unresolved_proxy_pass(&s.directives, inherited, upstreams)
+            => {
+                result.push(dir.position);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Main {
+    /// Returns names of locations referenced as `@name` which don't exist
+    ///
+    /// References are collected from `try_files` and `error_page` and are
+    /// looked up within the same `server` block only, as nginx does.
+    pub fn undefined_named_locations(&self) -> Vec<&str> {
+        let mut result = Vec::new();
+        for dir in self.all_directives() {
+            let server = match dir.item {
+                Item::Server(ref s) => s,
+                _ => continue,
+            };
+            let mut defined = HashSet::new();
+            let mut used = Vec::new();
+            for dir in DirectiveIter::depth_first(&server.directives) {
+                match dir.item {
+                    Item::Location(ref loc) => {
+                        if let LocationPattern::Named(ref name) = loc.pattern {
+                            defined.insert(&name[..]);
+                        }
+                    }
+                    Item::TryFiles(ref tf) => {
+                        if let TryFilesLastOption::NamedLocation(ref name)
+                            = tf.last_option
+                        {
+                            used.push(&name[..]);
+                        }
+                    }
+                    Item::ErrorPage(ref ep) => {
+                        used.extend(ep.named_location());
+                    }
+                    _ => {}
+                }
+            }
+            result.extend(used.into_iter().filter(|n| !defined.contains(n)));
+        }
+        result
+    }
+
+    /// Returns positions of `location` blocks having both `root` and `alias`
+    ///
+    /// Nginx refuses to start with such configuration.
+    pub fn root_alias_conflicts(&self) -> Vec<Pos> {
+        self.all_directives().filter(|dir| {
+            let loc = match dir.item {
+                Item::Location(ref loc) => loc,
+                _ => return false,
+            };
+            let dirs = &loc.directives;
+            dirs.iter().any(|d| matches!(d.item, Item::Root(..))) &&
+                dirs.iter().any(|d| matches!(d.item, Item::Alias(..)))
+        }).map(|dir| dir.position).collect()
+    }
+
+    /// Returns positions of `server` blocks that listen with `ssl` but have
+    /// no `ssl_certificate`
+    ///
+    /// Certificate might be either in the server itself or inherited from
+    /// the `http` block.
+    pub fn ssl_without_cert(&self) -> Vec<Pos> {
+        let mut result = Vec::new();
+        check_ssl(&self.directives, false, &mut result);
+        result
+    }
+
+    /// Returns positions of `default_type` values which aren't MIME types
+    ///
+    /// A value is considered a MIME type if it contains `/`. Nginx accepts
+    /// anything there, but a typo like `default_type text;` results in
+    /// clients getting an unusable `Content-Type`.
+    pub fn invalid_default_types(&self) -> Vec<Pos> {
+        self.all_directives().filter(|dir| match dir.item {
+            Item::DefaultType(ref val) => !val.to_raw_string().contains('/'),
+            _ => false,
+        }).map(|dir| dir.position).collect()
+    }
+
+    /// Returns positions of `proxy_pass` whose URI disagrees with location
+    /// about the trailing slash
+    ///
+    /// When `proxy_pass` has a URI part, nginx replaces the matched
+    /// location prefix with it. So `location /api/` with
+    /// `proxy_pass http://app/v1;` turns `/api/x` into `/v1x`, and
+    /// `location /api` with `proxy_pass http://app/v1/;` turns it into
+    /// `/v1//x`. Only prefix locations are checked, and urls ending with a
+    /// variable are skipped.
+    pub fn proxy_pass_slash_mismatches(&self) -> Vec<Pos> {
+        let mut result = Vec::new();
+        for dir in self.all_directives() {
+            let loc = match dir.item {
+                Item::Location(ref loc) => loc,
+                _ => continue,
+            };
+            let prefix = match loc.pattern {
+                LocationPattern::Prefix(ref p) => p,
+                LocationPattern::FinalPrefix(ref p) => p,
+                _ => continue,
+            };
+            for dir in &loc.directives {
+                let pass = match dir.item {
+                    Item::ProxyPass(ref p) if p.has_uri_component() => p,
+                    _ => continue,
+                };
+                let url = match pass.url.data.last() {
+                    Some(value::Item::Literal(s)) => s,
+                    _ => continue,
+                };
+                if prefix.ends_with('/') != url.ends_with('/') {
+                    result.push(dir.position);
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns positions of `server_tokens on`
+    ///
+    /// This makes nginx send its version in the `Server` header and on
+    /// error pages. Note that `on` is also the default, so a config
+    /// having no `server_tokens` at all isn't flagged.
+    pub fn exposes_server_tokens(&self) -> Vec<Pos> {
+        self.all_directives().filter(|dir| {
+            matches!(dir.item, Item::ServerTokens(ServerTokens::On))
+        }).map(|dir| dir.position).collect()
+    }
+
+    /// Returns positions of `merge_slashes off`
+    ///
+    /// With slashes not merged `//etc/passwd` doesn't match `location /etc/`
+    /// and is passed to the backend as is, which might be unexpected for
+    /// access rules or for backends normalizing paths on their own.
+    pub fn merge_slashes_disabled(&self) -> Vec<Pos> {
+        self.all_directives().filter(|dir| {
+            matches!(dir.item, Item::MergeSlashes(false))
+        }).map(|dir| dir.position).collect()
+    }
+
+    /// Returns positions of `server` blocks having `proxy_pass` with
+    /// variables but no `resolver`
+    ///
+    /// With variables in the url nginx resolves the host at run time, and
+    /// fails requests if there is no `resolver` in the location, server or
+    /// `http` block. Hosts which are IP addresses, unix sockets or names of
+    /// `upstream` blocks don't need a resolver. If the host itself is a
+    /// variable, a resolver is assumed to be needed.
+    pub fn dynamic_proxy_pass_without_resolver(&self) -> Vec<Pos> {
+        let upstreams = self.all_directives().filter_map(|dir| {
+            match dir.item {
+                Item::Upstream(ref u) => Some(&u.name[..]),
+                _ => None,
+            }
+        }).collect();
+        let mut result = Vec::new();
+        check_resolver(&self.directives, false, &upstreams, &mut result);
+        result
+    }
+
+    /// Returns positions of `if` blocks in `location` doing something risky
+    ///
+    /// Only `return` and `rewrite ... last` are considered safe inside `if`
+    /// in location context. Anything else, like `proxy_pass` or
+    /// `add_header`, might not work as expected, see
+    /// [If is Evil](https://www.nginx.com/resources/wiki/start/topics/depth/ifisevil/).
+    /// A `rewrite ... last` isn't reported here, but it rarely does what
+    /// was meant either, use
+    /// [`rewrite_last_in_if`](#method.rewrite_last_in_if) to find those.
+    pub fn risky_if_usage(&self) -> Vec<Pos> {
+        let mut result = Vec::new();
+        for dir in self.all_directives() {
+            let loc = match dir.item {
+                Item::Location(ref loc) => loc,
+                _ => continue,
+            };
+            for dir in &loc.directives {
+                if let Item::If(ref cond) = dir.item {
+                    if !cond.directives.iter().all(safe_in_if) {
+                        result.push(dir.position);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns positions of `rewrite ... last` in `if` in `location`
+    ///
+    /// In location context `last` doesn't just stop rewriting like `break`
+    /// does, but starts a new search of location for the rewritten URI.
+    /// If the URI still matches the same location, the `if` is evaluated
+    /// again, and nginx returns 500 after 10 cycles. Usually `break` is
+    /// what was meant there.
+    pub fn rewrite_last_in_if(&self) -> Vec<Pos> {
+        let mut result = Vec::new();
+        for dir in self.all_directives() {
+            let loc = match dir.item {
+                Item::Location(ref loc) => loc,
+                _ => continue,
+            };
+            for dir in &loc.directives {
+                let cond = match dir.item {
+                    Item::If(ref cond) => cond,
+                    _ => continue,
+                };
+                result.extend(cond.directives.iter().filter(|d| {
+                    matches!(d.item, Item::Rewrite(ref rw)
+                        if rw.flag == Some(RewriteFlag::Last))
+                }).map(|d| d.position));
+            }
+        }
+        result
+    }
+
+    /// Returns variables which are neither builtin nor defined in config
+    ///
+    /// Each name (without `$`) is reported once, in order of first use.
+    /// See [`variables_defined`](#method.variables_defined) for what is
+    /// considered a definition.
+    pub fn unknown_variables(&self) -> Vec<&str> {
+        let defined = self.variables_defined();
+        let mut seen = HashSet::new();
+        let mut result = Vec::new();
+        for dir in self.all_directives() {
+            dir.item.visit_values(|val| {
+                for name in val.variables() {
+                    if !variables::is_builtin(name) &&
+                        !defined.contains(name) && seen.insert(name)
+                    {
+                        result.push(name);
+                    }
+                }
+            });
+        }
+        result
+    }
+}
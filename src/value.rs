@@ -1,12 +1,18 @@
+#![allow(ellipsis_inclusive_range_patterns)] // `...` is used throughout
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::mem;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use combine::easy::Error;
 use combine::error::StreamError;
 
-use format::{Displayable, Formatter};
+use format::{Displayable, Formatter, Style};
 use position::Pos;
 use tokenizer::Token;
+use variables;
 
 /// Generic string value
 ///
@@ -14,16 +20,71 @@ use tokenizer::Token;
 ///
 /// Some string parts might originally be escaped or quoted. We get rid of
 /// quotes when parsing
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Values are compared by their contents, position is ignored.
+#[derive(Debug, Clone, Eq)]
 pub struct Value {
     position: Pos,
+    quoted: bool,
     pub(crate) data: Vec<Item>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum Item {
     Literal(String),
-    Variable(String),
+    /// Name is shared between all references of the same variable parsed
+    /// within single `Interning` scope
+    Variable(Arc<str>),
+}
+
+thread_local! {
+    static INTERNER: RefCell<Option<HashSet<Arc<str>>>> =
+        const { RefCell::new(None) };
+}
+
+/// Makes variable names parsed while it's alive to share memory
+///
+/// Big configs reference `$host` or `$uri` thousands of times, each
+/// reference would be a separate allocation otherwise. Names are only
+/// shared within a scope, so memory isn't held after parsing is finished.
+/// Nested scopes reuse the outer one.
+pub(crate) struct Interning {
+    outer: bool,
+}
+
+impl Interning {
+    pub fn start() -> Interning {
+        INTERNER.with(|cell| {
+            let mut interner = cell.borrow_mut();
+            let outer = interner.is_none();
+            if outer {
+                *interner = Some(HashSet::new());
+            }
+            Interning { outer }
+        })
+    }
+}
+
+impl Drop for Interning {
+    fn drop(&mut self) {
+        if self.outer {
+            INTERNER.with(|cell| cell.borrow_mut().take());
+        }
+    }
+}
+
+fn variable(name: &str) -> Item {
+    INTERNER.with(|cell| match *cell.borrow_mut() {
+        Some(ref mut names) => {
+            if let Some(name) = names.get(name) {
+                return Item::Variable(name.clone());
+            }
+            let name: Arc<str> = Arc::from(name);
+            names.insert(name.clone());
+            Item::Variable(name)
+        }
+        None => Item::Variable(Arc::from(name)),
+    })
 }
 
 
@@ -36,6 +97,7 @@ impl Value {
     pub(crate) fn parse_str<'a>(position: Pos, token: &str)
         -> Result<Value, Error<Token<'a>, Token<'a>>>
     {
+        let quoted = token.starts_with('"') || token.starts_with('\'');
         let data = if token.starts_with('"') {
             Value::scan_quoted('"', token)?
         } else if token.starts_with("'") {
@@ -43,13 +105,28 @@ impl Value {
         } else {
             Value::scan_raw(token)?
         };
-        Ok(Value { position, data })
+        Ok(Value { position, quoted, data })
+    }
+
+    /// A regex, kept as a single literal as `$` in a regex isn't a variable
+    pub(crate) fn regex(position: Pos, token: &str) -> Value {
+        let quoted = token.starts_with('"') || token.starts_with('\'');
+        let text = if quoted { &token[1..token.len()-1] } else { token };
+        let data = vec![Item::Literal(text.to_string())];
+        Value { position, quoted, data }
     }
 
     fn scan_raw<'a>(value: &str)
         -> Result<Vec<Item>, Error<Token<'a>, Token<'a>>>
     {
         use self::Item::*;
+        // fast path: most values have no variables at all
+        if !value.contains('$') {
+            if value.is_empty() {
+                return Ok(Vec::new());
+            }
+            return Ok(vec![Literal(value.to_string())]);
+        }
         let mut buf = Vec::new();
         let mut chiter = value.char_indices().peekable();
         let mut prev_char = ' ';  // any having no special meaning
@@ -82,9 +159,9 @@ impl Value {
                                 };
                             }
                             let now = chiter.peek().map(|&(idx, _)| idx)
-                                .unwrap();
-                            buf.push(Variable(
-                                value[vstart+1..now].to_string()));
+                                .ok_or_else(|| Error::unexpected_message(
+                                    "unclosed variable brace"))?;
+                            buf.push(variable(&value[vstart+1..now]));
                             cur_slice = now+1;
                         }
                         'a'...'z' | 'A'...'Z' | '_' | '0'...'9' => {
@@ -97,8 +174,7 @@ impl Value {
                             }
                             let now = chiter.peek().map(|&(idx, _)| idx)
                                 .unwrap_or(value.len());
-                            buf.push(Variable(
-                                value[vstart..now].to_string()));
+                            buf.push(variable(&value[vstart..now]));
                             cur_slice = now;
                         }
                         _ => {
@@ -115,7 +191,7 @@ impl Value {
         if cur_slice != value.len() {
             buf.push(Literal(value[cur_slice..].to_string()));
         }
-        Ok(buf)
+        Ok(join_literals(buf))
     }
 
     fn scan_quoted<'a>(quote: char, value: &str)
@@ -126,11 +202,13 @@ impl Value {
         let mut chiter = value.char_indices().peekable();
         chiter.next(); // skip quote
         let mut prev_char = ' ';  // any having no special meaning
-        let mut cur_slice = String::new();
+        // the first literal is usually the only one, so reserve enough for it
+        let mut cur_slice = String::with_capacity(value.len());
         while let Some((idx, cur_char)) = chiter.next() {
             match cur_char {
                 _ if prev_char == '\\' => {
                     cur_slice.push(cur_char);
+                    prev_char = ' ';
                     continue;
                 }
                 '"' | '\'' if cur_char == quote => {
@@ -144,7 +222,7 @@ impl Value {
                         return Err(Error::unexpected_message(
                             "quote closes prematurely"));
                     }
-                    return Ok(buf);
+                    return Ok(join_literals(buf));
                 }
                 '$' => {
                     let vstart = idx + 1;
@@ -157,7 +235,24 @@ impl Value {
                             "bare $ in expression"))?;
                     match fchar {
                         '{' => {
-                            unimplemented!();
+                            while let Some(&(_, c)) = chiter.peek() {
+                                match c {
+                                    'a'...'z' | 'A'...'Z' | '_' | '0'...'9'
+                                    => chiter.next(),
+                                    '}' => break,
+                                    _ if c == quote => {
+                                        return Err(Error::unexpected_message(
+                                            "unclosed variable brace"));
+                                    }
+                                    _ => {
+                                        return Err(Error::expected("}".into()));
+                                    }
+                                };
+                            }
+                            let now = chiter.next().map(|(idx, _)| idx)
+                                .ok_or_else(|| Error::unexpected_message(
+                                    "unclosed variable brace"))?;
+                            buf.push(variable(&value[vstart+1..now]));
                         }
                         'a'...'z' | 'A'...'Z' | '_' | '0'...'9' => {
                             while let Some(&(_, c)) = chiter.peek() {
@@ -171,8 +266,7 @@ impl Value {
                                 .ok_or_else(|| {
                                     Error::unexpected_message("unclosed quote")
                                 })?;
-                            buf.push(Variable(
-                                value[vstart..now].to_string()));
+                            buf.push(variable(&value[vstart..now]));
                         }
                         _ => {
                             return Err(Error::unexpected_message(
@@ -189,6 +283,12 @@ impl Value {
     }
 }
 
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        self.data == other.data
+    }
+}
+
 impl FromStr for Value {
     type Err = String;
     fn from_str(s: &str) -> Result<Value, String> {
@@ -198,6 +298,24 @@ impl FromStr for Value {
 }
 
 impl Value {
+    /// Returns true if the value must be quoted to be written in config
+    ///
+    /// That is if it's empty, starts with `#` or contains whitespace, `;`,
+    /// `{`, `}`, a quote or a backslash. If this returns false, the value is
+    /// read back the same when written bare. Variables aren't looked into,
+    /// as their values are substituted by nginx after parsing.
+    pub fn needs_quoting(&self) -> bool {
+        self.data.is_empty() || self.starts_comment() || self.has_specials()
+    }
+
+    /// A bare `#` at the start of a token starts a comment
+    fn starts_comment(&self) -> bool {
+        match self.data.first() {
+            Some(Item::Literal(x)) => x.starts_with('#'),
+            _ => false,
+        }
+    }
+
     fn has_specials(&self) -> bool {
         use self::Item::*;
         for item in &self.data {
@@ -205,9 +323,9 @@ impl Value {
                 Literal(ref x) => {
                     for c in x.chars() {
                         match c {
-                            ' ' | ';' | '\r' | '\n' | '\t' | '{' | '}' => {
-                                return true;
-                            }
+                            ' ' | ';' | '\r' | '\n' | '\t' | '{' | '}' |
+                            '\'' | '"' | '\\'
+                            => return true,
                             _ => {}
                         }
                     }
@@ -236,9 +354,121 @@ impl Value {
             *item = Literal(new_value);
         }
     }
+
+    /// Names of variables referenced in the value (without `$`)
+    pub fn variables(&self) -> impl Iterator<Item=&str> {
+        self.data.iter().filter_map(|item| match *item {
+            Item::Variable(ref name) => Some(&name[..]),
+            Item::Literal(..) => None,
+        })
+    }
+
+    /// Returns family of a dynamic variable (name without `$`)
+    ///
+    /// Nginx provides variables with arbitrary suffixes, like `$arg_page`
+    /// or `$http_user_agent`. For these the family, e.g. `"arg"`, `"http"`,
+    /// `"cookie"`, `"sent_http"` or `"upstream_http"`, is returned. Other
+    /// variables return `None`.
+    pub fn dynamic_variable_family(name: &str) -> Option<&'static str> {
+        variables::family(name)
+    }
+
+    /// Position of the value in the source text
+    pub fn position(&self) -> Pos {
+        self.position
+    }
+
+    /// Returns true if value is a path starting with `/`
+    ///
+    /// Values starting with a variable are not considered absolute, as
+    /// it's unknown what variable contains.
+    pub fn is_absolute_path(&self) -> bool {
+        match self.data.first() {
+            Some(Item::Literal(x)) => x.starts_with('/'),
+            _ => false,
+        }
+    }
+
+    /// Append a literal string to the end of the value
+    ///
+    /// The text is merged into the last item if that item is a literal too.
+    pub fn push_literal(&mut self, s: &str) {
+        use self::Item::*;
+        if let Some(&mut Literal(ref mut last)) = self.data.last_mut() {
+            last.push_str(s);
+            return;
+        }
+        self.data.push(Literal(s.to_string()));
+    }
+
+    /// Merges adjacent quoted values into one
+    ///
+    /// Nginx concatenates consecutive quoted strings in some directives,
+    /// for example `log_format main '$remote_addr ' '$request';` has a
+    /// single format `$remote_addr $request`. Unquoted values are left as
+    /// is.
+    pub fn join_quoted(values: Vec<Value>) -> Vec<Value> {
+        let mut result: Vec<Value> = Vec::with_capacity(values.len());
+        for val in values {
+            if val.quoted {
+                if let Some(last) = result.last_mut() {
+                    if last.quoted {
+                        last.append(val);
+                        continue;
+                    }
+                }
+            }
+            result.push(val);
+        }
+        result
+    }
+
+    /// Concatenates two values into one
+    ///
+    /// Adjacent literals are merged, so joining `$scheme://` and `$host`
+    /// and then `$uri` gives the same value as parsing `$scheme://$host$uri`.
+    /// Position of the value is taken from `self`.
+    pub fn join(mut self, other: Value) -> Value {
+        self.append(other);
+        self
+    }
+
+    /// Append contents of other value to the end of this one
+    pub(crate) fn append(&mut self, other: Value) {
+        self.data.extend(other.data);
+        self.data = join_literals(mem::take(&mut self.data));
+    }
+
+    /// Append a variable reference (without the `$`) to the end of the value
+    pub fn push_variable(&mut self, name: &str) {
+        self.data.push(variable(name));
+    }
+}
+
+/// Merges adjacent literals, so that equal values have equal `data`
+fn join_literals(items: Vec<Item>) -> Vec<Item> {
+    use self::Item::*;
+    if items.len() < 2 {
+        return items;
+    }
+    let mut result = Vec::with_capacity(items.len());
+    for item in items {
+        if let Literal(ref s) = item {
+            if let Some(&mut Literal(ref mut last)) = result.last_mut() {
+                last.push_str(s);
+                continue;
+            }
+        }
+        result.push(item);
+    }
+    result
 }
 
-fn next_alphanum(data: &Vec<Item>, index: usize) -> bool {
+/// Returns true if variable at index must be written as `${name}`
+///
+/// That is when the next literal would otherwise continue the name, or
+/// starts with `{` which makes `$name{` look like a broken `${name}`.
+fn needs_braces(data: &Vec<Item>, index: usize) -> bool {
     use self::Item::*;
     data.get(index+1).and_then(|item| {
         match item {
@@ -246,46 +476,148 @@ fn next_alphanum(data: &Vec<Item>, index: usize) -> bool {
             Variable(_) => None,
         }
     }).and_then(|s| {
-        s.chars().next().map(|c| c.is_alphanumeric())
+        s.chars().next().map(|c| c.is_alphanumeric() || c == '_' || c == '{')
     }).unwrap_or(false)
 }
 
-impl Displayable for Value {
-    fn display(&self, f: &mut Formatter) {
+/// Escapes `"` which aren't escaped yet, to put text in double quotes
+///
+/// Literals keep escapes as written, so a `"` could only be there bare if
+/// the value was single-quoted.
+pub(crate) fn escape_double_quotes(s: &str) -> Cow<'_, str> {
+    let mut result = String::new();
+    let mut escaped = false;
+    let mut start = 0;
+    for (idx, c) in s.char_indices() {
+        match c {
+            '"' if !escaped => {
+                result.push_str(&s[start..idx]);
+                result.push('\\');
+                start = idx;
+            }
+            '\\' => {
+                escaped = !escaped;
+                continue;
+            }
+            _ => {}
+        }
+        escaped = false;
+    }
+    if result.is_empty() {
+        return Cow::Borrowed(s);
+    }
+    result.push_str(&s[start..]);
+    Cow::Owned(result)
+}
+
+impl Value {
+    fn display_items(&self, f: &mut Formatter, quoted: bool) {
         use self::Item::*;
-        if self.data.is_empty() || self.has_specials() {
-            f.write("\"");
-            for (index, item) in self.data.iter().enumerate() {
-                match *item {
-                    // TODO(tailhook) escape special chars
-                    Literal(ref v) => f.write(v),
-                    Variable(ref v) if next_alphanum(&self.data, index) => {
-                        f.write("${");
-                        f.write(v);
-                        f.write("}");
-                    }
-                    Variable(ref v) => {
-                        f.write("$");
-                        f.write(v);
-                    }
+        for (index, item) in self.data.iter().enumerate() {
+            match *item {
+                Literal(ref v) if quoted => f.write(&escape_double_quotes(v)),
+                Literal(ref v) => f.write(v),
+                Variable(ref v) if needs_braces(&self.data, index) => {
+                    f.write("${");
+                    f.write(v);
+                    f.write("}");
+                }
+                Variable(ref v) => {
+                    f.write("$");
+                    f.write(v);
                 }
             }
+        }
+    }
+
+    /// Returns the value as text without surrounding quotes
+    ///
+    /// Unlike `to_string()` the value is never quoted, even if it contains
+    /// spaces or other special chars. Useful to embed the value into other
+    /// quoted text.
+    pub fn to_raw_string(&self) -> String {
+        let style = Style::default();
+        let mut buf = String::new();
+        let mut f = Formatter::new(&mut buf, &style);
+        self.display_items(&mut f, false);
+        f.finish().expect("writing to string never fails");
+        buf
+    }
+}
+
+impl Displayable for Value {
+    fn display(&self, f: &mut Formatter) {
+        if self.needs_quoting() {
+            f.write("\"");
+            self.display_items(f, true);
             f.write("\"");
         } else {
-            for (index, item) in self.data.iter().enumerate() {
-                match *item {
-                    Literal(ref v) => f.write(v),
-                    Variable(ref v) if next_alphanum(&self.data, index) => {
-                        f.write("${");
-                        f.write(v);
-                        f.write("}");
-                    }
-                    Variable(ref v) => {
-                        f.write("$");
-                        f.write(v);
-                    }
-                }
+            self.display_items(f, false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{Value, Item, Interning};
+    use super::Item::*;
+    use position::Pos;
+
+    fn items(s: &str) -> Vec<Item> {
+        Value::parse_str(Pos::default(), s).unwrap().data
+    }
+
+    #[test]
+    fn plain() {
+        assert_eq!(items("/var/www"), vec![Literal("/var/www".into())]);
+        assert_eq!(items("a$b"), vec![
+            Literal("a".into()),
+            Variable("b".into()),
+        ]);
+        assert_eq!(items("\"\""), vec![]);
+    }
+
+    #[test]
+    fn escaped_raw() {
+        assert_eq!(items(r"a\$b"), vec![Literal(r"a\$b".into())]);
+    }
+
+    #[test]
+    fn escaped_quoted() {
+        assert_eq!(items(r#""a\"b c""#), vec![Literal(r#"a\"b c"#.into())]);
+        assert_eq!(items(r#""a\"b $x""#), vec![
+            Literal(r#"a\"b "#.into()),
+            Variable("x".into()),
+        ]);
+    }
+
+    #[test]
+    fn braces_quoted() {
+        assert_eq!(items(r#""${host}name x""#), vec![
+            Variable("host".into()),
+            Literal("name x".into()),
+        ]);
+        assert_eq!(items("'${a}{'"), vec![
+            Variable("a".into()),
+            Literal("{".into()),
+        ]);
+        assert!(Value::parse_str(Pos::default(), r#""${a b}""#).is_err());
+        assert!(Value::parse_str(Pos::default(), r#""${a"#).is_err());
+    }
+
+    #[test]
+    fn interned() {
+        fn name(s: &str) -> Arc<str> {
+            match items(s).pop() {
+                Some(Variable(name)) => name,
+                _ => unreachable!(),
             }
         }
+        assert!(!Arc::ptr_eq(&name("$host"), &name("$host")));
+        let _names = Interning::start();
+        assert!(Arc::ptr_eq(&name("$host"), &name("/${host}")));
+        assert!(!Arc::ptr_eq(&name("$host"), &name("$uri")));
     }
 }
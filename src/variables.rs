@@ -0,0 +1,159 @@
+//! Variables provided by nginx itself
+//!
+//! The list covers the core http module and the modules parsed by this
+//! crate. Keep the lists sorted.
+
+
+/// Builtin variables, names without `$`
+const BUILTIN: &[&str] = &[
+    "ancient_browser",
+    "arg",
+    "args",
+    "binary_remote_addr",
+    "body_bytes_sent",
+    "bytes_sent",
+    "connection",
+    "connection_requests",
+    "content_length",
+    "content_type",
+    "document_root",
+    "document_uri",
+    "fastcgi_path_info",
+    "fastcgi_script_name",
+    "geoip_area_code",
+    "geoip_city",
+    "geoip_city_continent_code",
+    "geoip_city_country_code",
+    "geoip_city_country_code3",
+    "geoip_city_country_name",
+    "geoip_country_code",
+    "geoip_country_code3",
+    "geoip_country_name",
+    "geoip_dma_code",
+    "geoip_latitude",
+    "geoip_longitude",
+    "geoip_org",
+    "geoip_postal_code",
+    "geoip_region",
+    "geoip_region_name",
+    "host",
+    "hostname",
+    "https",
+    "is_args",
+    "limit_rate",
+    "modern_browser",
+    "msec",
+    "msie",
+    "nginx_version",
+    "pid",
+    "pipe",
+    "proxy_add_x_forwarded_for",
+    "proxy_host",
+    "proxy_port",
+    "proxy_protocol_addr",
+    "proxy_protocol_port",
+    "query_string",
+    "realip_remote_addr",
+    "realip_remote_port",
+    "realpath_root",
+    "remote_addr",
+    "remote_port",
+    "remote_user",
+    "request",
+    "request_body",
+    "request_body_file",
+    "request_completion",
+    "request_filename",
+    "request_id",
+    "request_length",
+    "request_method",
+    "request_time",
+    "request_uri",
+    "scheme",
+    "server_addr",
+    "server_name",
+    "server_port",
+    "server_protocol",
+    "ssl_cipher",
+    "ssl_client_cert",
+    "ssl_client_fingerprint",
+    "ssl_client_s_dn",
+    "ssl_client_verify",
+    "ssl_protocol",
+    "ssl_server_name",
+    "ssl_session_id",
+    "status",
+    "tcpinfo_rcv_space",
+    "tcpinfo_rtt",
+    "tcpinfo_rttvar",
+    "tcpinfo_snd_cwnd",
+    "time_iso8601",
+    "time_local",
+    "upstream_addr",
+    "upstream_bytes_received",
+    "upstream_cache_status",
+    "upstream_connect_time",
+    "upstream_header_time",
+    "upstream_response_length",
+    "upstream_response_time",
+    "upstream_status",
+    "uri",
+];
+
+/// Families of builtin variables, like `$http_user_agent`
+const BUILTIN_PREFIXES: &[&str] = &[
+    "arg_",
+    "cookie_",
+    "http_",
+    "sent_http_",
+    "sent_trailer_",
+    "upstream_cookie_",
+    "upstream_http_",
+    "upstream_trailer_",
+];
+
+/// Returns true if variable (without `$`) is provided by nginx
+///
+/// Regex captures like `$1` are considered builtin too.
+pub fn is_builtin(name: &str) -> bool {
+    BUILTIN.binary_search(&name).is_ok() ||
+        BUILTIN_PREFIXES.iter().any(|p| name.starts_with(p)) ||
+        (!name.is_empty() && name.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Returns family of a dynamic variable, e.g. `http` for `http_user_agent`
+pub fn family(name: &str) -> Option<&'static str> {
+    BUILTIN_PREFIXES.iter()
+        .find(|p| name.len() > p.len() && name.starts_with(*p))
+        .map(|p| &p[..p.len()-1])
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BUILTIN, is_builtin, family};
+
+    #[test]
+    fn sorted() {
+        let mut sorted = BUILTIN.to_vec();
+        sorted.sort();
+        assert_eq!(BUILTIN, &sorted[..]);
+    }
+
+    #[test]
+    fn builtin() {
+        assert!(is_builtin("host"));
+        assert!(is_builtin("http_user_agent"));
+        assert!(is_builtin("1"));
+        assert!(!is_builtin("hostt"));
+        assert!(!is_builtin(""));
+    }
+
+    #[test]
+    fn families() {
+        assert_eq!(family("sent_http_location"), Some("sent_http"));
+        assert_eq!(family("upstream_http_server"), Some("upstream_http"));
+        assert_eq!(family("cookie_sid"), Some("cookie"));
+        assert_eq!(family("http_"), None);
+        assert_eq!(family("host"), None);
+    }
+}
@@ -0,0 +1,167 @@
+//! Selection of a virtual server the way nginx does it
+//!
+//! See [How nginx processes a request][1] and [Server names][2].
+//!
+//! [1]: http://nginx.org/en/docs/http/request_processing.html
+//! [2]: http://nginx.org/en/docs/http/server_names.html
+use ast::{Main, Directive, Server, Item, Address, ServerName};
+use ast::EffectiveListens;
+
+
+fn port_of(addr: &Address) -> Option<u16> {
+    match *addr {
+        Address::Ip(sa) => Some(sa.port()),
+        Address::StarPort(port) | Address::Port(port) => Some(port),
+        Address::Unix(..) => None,
+    }
+}
+
+/// Returns `None` if server doesn't listen on the port, otherwise whether
+/// it's marked as `default_server` there
+fn listens_on(server: &Server, port: u16) -> Option<bool> {
+    match server.effective_listens() {
+        EffectiveListens::Default if port == 80 => Some(false),
+        EffectiveListens::Default => None,
+        EffectiveListens::Explicit(listens) => {
+            let mut result = None;
+            for lst in listens {
+                if port_of(&lst.address) == Some(port) {
+                    result = Some(result == Some(true) || lst.default_server);
+                }
+            }
+            result
+        }
+    }
+}
+
+/// Collects servers except ones in `stream` blocks
+fn http_servers<'a>(dirs: &'a [Directive], result: &mut Vec<&'a Server>) {
+    for dir in dirs {
+        match dir.item {
+            Item::Server(ref server) => result.push(server),
+            Item::Stream(..) => {}
+            ref item => if let Some(children) = item.children() {
+                http_servers(children, result);
+            },
+        }
+    }
+}
+
+fn names(server: &Server) -> impl Iterator<Item=&ServerName> {
+    server.directives.iter().filter_map(|dir| match dir.item {
+        Item::ServerName(ref names) => Some(names),
+        _ => None,
+    }).flat_map(|names| names.iter())
+}
+
+/// Length of the matching name starting with asterisk, like `*.example.org`
+fn head_wildcard(name: &ServerName, host: &str) -> Option<usize> {
+    match *name {
+        ServerName::StarSuffix(ref suffix) => {
+            let suffix = suffix.to_lowercase();
+            host.strip_suffix(&suffix[..])
+                .filter(|rest| rest.len() > 1 && rest.ends_with('.'))
+                .map(|_| suffix.len() + 2)
+        }
+        // `.example.org` matches both `example.org` and `*.example.org`
+        ServerName::Suffix(ref suffix) => {
+            let suffix = suffix.to_lowercase();
+            host.strip_suffix(&suffix[..])
+                .filter(|rest| rest.is_empty() || rest.ends_with('.'))
+                .map(|_| suffix.len() + 1)
+        }
+        _ => None,
+    }
+}
+
+/// Length of the matching name ending with asterisk, like `mail.*`
+fn tail_wildcard(name: &ServerName, host: &str) -> Option<usize> {
+    match *name {
+        ServerName::StarPrefix(ref prefix) => {
+            let prefix = prefix.to_lowercase();
+            host.strip_prefix(&prefix[..])
+                .filter(|rest| rest.len() > 1 && rest.starts_with('.'))
+                .map(|_| prefix.len() + 2)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(feature="regex")]
+fn regex_matches(name: &ServerName, host: &str) -> bool {
+    use regex::RegexBuilder;
+    match *name {
+        ServerName::Regex(ref re) => {
+            RegexBuilder::new(re).case_insensitive(true).build()
+                .map(|re| re.is_match(host))
+                .unwrap_or(false)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(not(feature="regex"))]
+fn regex_matches(_name: &ServerName, _host: &str) -> bool {
+    false
+}
+
+/// Returns the server having the longest name matched by `matcher`
+fn longest<'a, F>(servers: &[&'a Server], matcher: F) -> Option<&'a Server>
+    where F: Fn(&ServerName) -> Option<usize>,
+{
+    let mut best = None;
+    for &server in servers {
+        if let Some(len) = names(server).filter_map(&matcher).max() {
+            if best.map(|(best_len, _)| len > best_len).unwrap_or(true) {
+                best = Some((len, server));
+            }
+        }
+    }
+    best.map(|(_, server)| server)
+}
+
+impl Main {
+    /// Returns the `server` block nginx would choose for a request
+    ///
+    /// `host` is the value of the `Host` header without the port. Only
+    /// servers listening on `port` (on any address) are considered, and the
+    /// server without `listen` directives is assumed to listen on port 80.
+    /// Then names are tried in the order nginx does:
+    ///
+    /// 1. Exact name
+    /// 2. Longest wildcard name starting with an asterisk, `*.example.org`
+    ///    or `.example.org`
+    /// 3. Longest wildcard name ending with an asterisk, `mail.*`
+    /// 4. First matching regular expression, only if the `regex` feature
+    ///    is enabled, otherwise regular expressions never match
+    ///
+    /// If nothing matches, the `default_server` for the port is returned,
+    /// or the first server listening on the port if there is none.
+    pub fn server_for_host(&self, host: &str, port: u16) -> Option<&Server> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let mut all = Vec::new();
+        http_servers(&self.directives, &mut all);
+        let mut servers = Vec::new();
+        let mut default = None;
+        for server in all {
+            match listens_on(server, port) {
+                Some(true) if default.is_none() => default = Some(server),
+                Some(_) => {}
+                None => continue,
+            }
+            servers.push(server);
+        }
+        let exact = servers.iter().find(|srv| names(srv).any(|name| {
+            matches!(*name,
+                ServerName::Exact(ref n) if n.to_lowercase() == host)
+        }));
+        exact.cloned()
+            .or_else(|| longest(&servers, |name| head_wildcard(name, &host)))
+            .or_else(|| longest(&servers, |name| tail_wildcard(name, &host)))
+            .or_else(|| servers.iter().find(|srv| {
+                names(srv).any(|name| regex_matches(name, &host))
+            }).cloned())
+            .or(default)
+            .or_else(|| servers.first().cloned())
+    }
+}
@@ -0,0 +1,34 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::net::IpAddr;
+
+use nginx_config::parse_directives;
+use nginx_config::ast::{Item, Satisfy, Source};
+
+
+fn item(text: &str) -> Item {
+    parse_directives(text).unwrap().pop().unwrap().item
+}
+
+#[test]
+fn satisfy() {
+    assert_eq!(item("satisfy all;"), Item::Satisfy(Satisfy::All));
+    assert_eq!(item("satisfy any;"), Item::Satisfy(Satisfy::Any));
+    assert!(parse_directives("satisfy some;").is_err());
+    assert!(parse_directives("satisfy;").is_err());
+    assert_eq!(Satisfy::Any.to_string(), "any");
+}
+
+#[test]
+fn ipv6_network() {
+    let addr = "2001:db8::".parse::<IpAddr>().unwrap();
+    assert_eq!(item("deny 2001:db8::/32;"),
+        Item::Deny(Source::Network(addr, 32)));
+    assert_eq!(item("allow ::1;"),
+        Item::Allow(Source::Ip("::1".parse().unwrap())));
+    assert_eq!(item("deny 2001:db8::/128;"),
+        Item::Deny(Source::Network(addr, 128)));
+    assert!(parse_directives("deny 2001:db8::/129;").is_err());
+    assert!(parse_directives("deny 10.0.0.0/33;").is_err());
+}
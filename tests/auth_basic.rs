@@ -0,0 +1,22 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+use nginx_config::ast::Item;
+
+
+#[test]
+fn auth_basic() {
+    let ast = parse_main(r#"
+        auth_basic "Restricted $host";
+        auth_basic_user_file conf/htpasswd;
+        auth_basic off;
+    "#).unwrap();
+    let items = ast.directives.into_iter().map(|d| d.item)
+        .collect::<Vec<_>>();
+    assert_eq!(items, vec![
+        Item::AuthBasic(Some("'Restricted $host'".parse().unwrap())),
+        Item::AuthBasicUserFile("conf/htpasswd".parse().unwrap()),
+        Item::AuthBasic(None),
+    ]);
+}
@@ -0,0 +1,23 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+use nginx_config::ast::Item;
+
+
+fn item(text: &str) -> Item {
+    parse_main(text).unwrap().directives.remove(0).item
+}
+
+#[test]
+fn charset() {
+    assert_eq!(item("charset utf-8;"), Item::Charset(Some("utf-8".into())));
+    assert_eq!(item("charset off;"), Item::Charset(None));
+    assert!(parse_main("charset;").is_err());
+}
+
+#[test]
+fn charset_types() {
+    assert_eq!(item("charset_types text/html text/xml;"),
+               Item::CharsetTypes(vec!["text/html".into(), "text/xml".into()]));
+}
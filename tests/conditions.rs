@@ -0,0 +1,113 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+use nginx_config::ast::{Item, IfCondition, FileTestKind};
+
+
+fn condition(text: &str) -> IfCondition {
+    let text = format!("if {} {{ }}", text);
+    match parse_main(&text).unwrap().directives.remove(0).item {
+        Item::If(i) => i.condition,
+        item => panic!("not an if: {:?}", item),
+    }
+}
+
+fn file_test(negated: bool, kind: FileTestKind, operand: &str)
+    -> IfCondition
+{
+    IfCondition::FileTest { negated, kind, operand: operand.parse().unwrap() }
+}
+
+#[test]
+fn not_exists() {
+    assert_eq!(condition("(!-e $request_filename)"),
+        file_test(true, FileTestKind::Exists, "$request_filename"));
+}
+
+#[test]
+fn file_tests() {
+    use nginx_config::ast::FileTestKind::*;
+    assert_eq!(condition("(-f $x)"), file_test(false, File, "$x"));
+    assert_eq!(condition("(!-f $x)"), file_test(true, File, "$x"));
+    assert_eq!(condition("(-d $x)"), file_test(false, Dir, "$x"));
+    assert_eq!(condition("(!-d $x)"), file_test(true, Dir, "$x"));
+    assert_eq!(condition("(-e /a/$x)"), file_test(false, Exists, "/a/$x"));
+    assert_eq!(condition("(-x $x)"), file_test(false, Executable, "$x"));
+    assert_eq!(condition("(!-x $x)"), file_test(true, Executable, "$x"));
+    assert!(parse_main("if (-z $x) { }").is_err());
+    assert!(parse_main("if (!-f $x $y) { }").is_err());
+}
+
+fn regex(variable: &str, pattern: &str, negated: bool, case_insensitive: bool)
+    -> IfCondition
+{
+    IfCondition::Regex {
+        variable: variable.parse().unwrap(),
+        pattern: pattern.to_string(),
+        negated,
+        case_insensitive,
+    }
+}
+
+#[test]
+fn regexes() {
+    assert_eq!(condition("($uri ~ ^/a)"), regex("$uri", "^/a", false, false));
+    assert_eq!(condition("($uri ~* ^/a)"), regex("$uri", "^/a", false, true));
+    assert_eq!(condition("($uri !~ ^/a)"), regex("$uri", "^/a", true, false));
+    assert_eq!(condition("($uri !~* ^/a)"), regex("$uri", "^/a", true, true));
+}
+
+#[test]
+fn regex_dollar_is_literal() {
+    assert_eq!(condition("($http_user_agent ~* (bot|spider)$)"),
+        regex("$http_user_agent", "(bot|spider)$", false, true));
+    assert_eq!(condition("($request_uri ~ ^/item/(\\d+)$)"),
+        regex("$request_uri", "^/item/(\\d+)$", false, false));
+}
+
+#[test]
+fn quoted_regex() {
+    assert_eq!(condition(r#"($uri ~ "^/a{2}$")"#),
+        regex("$uri", "^/a{2}$", false, false));
+}
+
+#[test]
+fn quoted_url_equality() {
+    let origin = "$http_origin".parse().unwrap();
+    assert_eq!(condition(r#"($http_origin = "https://example.com")"#),
+        IfCondition::Eq(origin, "https://example.com".parse().unwrap()));
+    let text = "if ($http_origin != 'https://a.example.com:8443') { }";
+    let main = parse_main(text).unwrap();
+    assert_eq!(main.to_string(),
+        "if ($http_origin != https://a.example.com:8443) {\n}\n");
+    let cond = condition("($host = $server_name)");
+    assert_eq!(cond, IfCondition::Eq(
+        "$host".parse().unwrap(), "$server_name".parse().unwrap()));
+}
+
+#[test]
+fn display() {
+    let roundtrip = |text: &str| condition(text).to_string();
+    assert_eq!(roundtrip("($x)"), "$x");
+    assert_eq!(roundtrip("($x = y)"), "$x = y");
+    assert_eq!(roundtrip("($x != 'a b')"), "$x != \"a b\"");
+    assert_eq!(roundtrip("($x = '')"), "$x = \"\"");
+    assert_eq!(roundtrip("($x = \"#x\")"), "$x = \"#x\"");
+    assert_eq!(roundtrip("($x != '#a')"), "$x != \"#a\"");
+    assert_eq!(roundtrip("($x !~* ^/a$)"), "$x !~* ^/a$");
+    assert_eq!(roundtrip("($uri ~ '^/a{2}$')"), "$uri ~ \"^/a{2}$\"");
+    assert_eq!(roundtrip("(!-f $request_filename)"),
+        "!-f $request_filename");
+    assert_eq!(roundtrip("(-x /bin/$x)"), "-x /bin/$x");
+}
+
+#[test]
+fn quotes_in_operand() {
+    let roundtrip = |text: &str| condition(text).to_string();
+    assert_eq!(roundtrip(r#"($x = 'say "hi"')"#), r#"$x = "say \"hi\"""#);
+    assert_eq!(roundtrip(r#"($x = "it's")"#), r#"$x = "it's""#);
+    let printed = roundtrip(r#"($x = 'a\\"b')"#);
+    assert_eq!(printed, r#"$x = "a\\\"b""#);
+    assert_eq!(roundtrip(&format!("({})", printed)), printed);
+}
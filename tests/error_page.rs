@@ -0,0 +1,32 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_directives;
+use nginx_config::ast::{Item, ErrorPage, ErrorPageResponse};
+
+
+fn error_page(text: &str) -> ErrorPage {
+    match parse_directives(text).unwrap().pop().unwrap().item {
+        Item::ErrorPage(ep) => ep,
+        item => panic!("not an error_page: {:?}", item),
+    }
+}
+
+#[test]
+fn named_location() {
+    let ep = error_page("error_page 404 @notfound;");
+    assert_eq!(ep.codes, vec![404]);
+    assert_eq!(ep.response_code, ErrorPageResponse::Target);
+    assert_eq!(ep.named_location(), Some("notfound"));
+    let ep = error_page("error_page 500 502 =200 @fallback;");
+    assert_eq!(ep.response_code, ErrorPageResponse::Replace(200));
+    assert_eq!(ep.named_location(), Some("fallback"));
+}
+
+#[test]
+fn not_named_location() {
+    assert_eq!(error_page("error_page 404 /404.html;").named_location(),
+        None);
+    assert_eq!(error_page("error_page 404 /@x;").named_location(), None);
+    assert_eq!(error_page("error_page 404 @$x;").named_location(), None);
+}
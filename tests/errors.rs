@@ -30,3 +30,25 @@ fn test_error(filename: &str) {
 #[test] fn invalid_directive_with_newline() {
     test_error("invalid_directive_with_newline");
 }
+#[test] fn missing_semicolon() { test_error("missing_semicolon"); }
+#[test] fn missing_semicolon_at_eof() { test_error("missing_semicolon_at_eof"); }
+#[test] fn gzip_comp_level() { test_error("gzip_comp_level"); }
+#[test] fn unclosed_variable_brace() { test_error("unclosed_variable_brace"); }
+#[test] fn unclosed_variable_brace_quoted() {
+    test_error("unclosed_variable_brace_quoted");
+}
+
+#[test]
+fn std_error() {
+    use std::error::Error;
+
+    fn parse(text: &str) -> Result<usize, Box<dyn Error>> {
+        let ast = parse_main(text)?;
+        Ok(ast.directives.len())
+    }
+    assert_eq!(parse("daemon on;").unwrap(), 1);
+    let err = parse("daemon on").unwrap_err();
+    assert_eq!(err.to_string(),
+        "parse error: Parse error at 1:10\nUnexpected `end of input`\n\
+         Expected `;`\n");
+}
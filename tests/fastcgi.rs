@@ -0,0 +1,56 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_directives;
+use nginx_config::ast::{Item, Value, NameValue};
+
+
+fn items(text: &str) -> Vec<Item> {
+    parse_directives(text).unwrap().into_iter().map(|d| d.item).collect()
+}
+
+fn val(text: &str) -> Value {
+    text.parse().unwrap()
+}
+
+#[test]
+fn php_fpm_location() {
+    assert_eq!(items("
+        fastcgi_pass 127.0.0.1:9000;
+        fastcgi_index index.php;
+        fastcgi_param SCRIPT_FILENAME $document_root$fastcgi_script_name;
+        fastcgi_param HTTPS $https if_not_empty;
+    "), vec![
+        Item::FastcgiPass(val("127.0.0.1:9000")),
+        Item::FastcgiIndex(val("index.php")),
+        Item::FastcgiParam {
+            param: NameValue {
+                name: val("SCRIPT_FILENAME"),
+                value: val("$document_root$fastcgi_script_name"),
+            },
+            if_not_empty: false,
+        },
+        Item::FastcgiParam {
+            param: NameValue { name: val("HTTPS"), value: val("$https") },
+            if_not_empty: true,
+        },
+    ]);
+}
+
+#[test]
+fn param_variables() {
+    let items = items(
+        "fastcgi_param SCRIPT_FILENAME $document_root$fastcgi_script_name;");
+    match items[0] {
+        Item::FastcgiParam { ref param, .. } => {
+            assert_eq!(param.value.variables().collect::<Vec<_>>(),
+                vec!["document_root", "fastcgi_script_name"]);
+        }
+        ref item => panic!("not a fastcgi_param: {:?}", item),
+    }
+}
+
+#[test]
+fn param_needs_value() {
+    assert!(parse_directives("fastcgi_param SCRIPT_FILENAME;").is_err());
+}
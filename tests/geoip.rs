@@ -0,0 +1,48 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::{parse_directives, parse_main_with, ParseOptions};
+use nginx_config::visitors::visit_mutable;
+use nginx_config::ast::Item;
+
+
+fn item(text: &str) -> Item {
+    parse_directives(text).unwrap().pop().unwrap().item
+}
+
+#[test]
+fn typed() {
+    assert_eq!(item("geoip_country /usr/share/GeoIP/GeoIP.dat;"),
+        Item::GeoipCountry("/usr/share/GeoIP/GeoIP.dat".parse().unwrap()));
+    assert_eq!(item("geoip_city /usr/share/GeoIP/GeoLiteCity.dat;"),
+        Item::GeoipCity("/usr/share/GeoIP/GeoLiteCity.dat".parse().unwrap()));
+    assert_eq!(item("geoip_org /usr/share/GeoIP/GeoIPASNum.dat;"),
+        Item::GeoipOrg("/usr/share/GeoIP/GeoIPASNum.dat".parse().unwrap()));
+    assert!(parse_directives("geoip_country;").is_err());
+    assert!(parse_directives("geoip_country a b;").is_err());
+}
+
+#[test]
+fn collect_db_paths() {
+    let mut ast = parse_main_with(r#"
+        http {
+            geoip_country /usr/share/GeoIP/GeoIP.dat;
+            geoip2 /etc/maxmind/GeoLite2-City.mmdb {
+                $geoip2_city_name city names en;
+            }
+        }
+    "#, &ParseOptions::default()).unwrap();
+    let mut paths = Vec::new();
+    visit_mutable(&mut ast.directives, |dir| {
+        dir.visit_values_mut(|v| {
+            let raw = v.to_raw_string();
+            if raw.ends_with(".dat") || raw.ends_with(".mmdb") {
+                paths.push(raw);
+            }
+        });
+    });
+    assert_eq!(paths, vec![
+        "/usr/share/GeoIP/GeoIP.dat",
+        "/etc/maxmind/GeoLite2-City.mmdb",
+    ]);
+}
@@ -0,0 +1,33 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_directives;
+use nginx_config::ast::Item;
+
+
+fn item(text: &str) -> Item {
+    parse_directives(text).unwrap().pop().unwrap().item
+}
+
+#[test]
+fn types() {
+    assert_eq!(item("gzip_types text/css application/json;"),
+        Item::GzipTypes(vec!["text/css".into(), "application/json".into()]));
+    assert!(parse_directives("gzip_types;").is_err());
+}
+
+#[test]
+fn comp_level() {
+    assert_eq!(item("gzip_comp_level 1;"), Item::GzipCompLevel(1));
+    assert_eq!(item("gzip_comp_level 9;"), Item::GzipCompLevel(9));
+    assert!(parse_directives("gzip_comp_level 0;").is_err());
+    assert!(parse_directives("gzip_comp_level 10;").is_err());
+    assert!(parse_directives("gzip_comp_level fast;").is_err());
+}
+
+#[test]
+fn min_length() {
+    assert_eq!(item("gzip_min_length 20;"), Item::GzipMinLength(20));
+    assert_eq!(item("gzip_min_length 2k;"), Item::GzipMinLength(2048));
+    assert!(parse_directives("gzip_min_length big;").is_err());
+}
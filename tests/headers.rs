@@ -0,0 +1,58 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_directives;
+use nginx_config::ast::{Item, AddHeader, NameValue};
+
+
+fn item(text: &str) -> Item {
+    parse_directives(text).unwrap().pop().unwrap().item
+}
+
+fn name_value(name: &str, value: &str) -> NameValue {
+    NameValue {
+        name: name.parse().unwrap(),
+        value: value.parse().unwrap(),
+    }
+}
+
+fn header(name: &str, value: &str, always: bool) -> AddHeader {
+    AddHeader { header: name_value(name, value), always }
+}
+
+#[test]
+fn add_header() {
+    assert_eq!(item("add_header X-Frame-Options DENY;"),
+        Item::AddHeader(header("X-Frame-Options", "DENY", false)));
+    assert_eq!(item("add_header X-Frame-Options DENY always;"),
+        Item::AddHeader(header("X-Frame-Options", "DENY", true)));
+}
+
+#[test]
+fn add_trailer() {
+    assert_eq!(item("add_trailer Server-Timing $request_time;"),
+        Item::AddTrailer(header("Server-Timing", "$request_time", false)));
+    assert_eq!(item("add_trailer Server-Timing $request_time always;"),
+        Item::AddTrailer(header("Server-Timing", "$request_time", true)));
+    assert!(parse_directives("add_trailer Server-Timing;").is_err());
+}
+
+#[test]
+fn proxy_set_header() {
+    assert_eq!(item("proxy_set_header Host $host;"),
+        Item::ProxySetHeader(name_value("Host", "$host")));
+    assert!(parse_directives("proxy_set_header Host;").is_err());
+}
+
+#[test]
+fn display() {
+    for text in &[
+        "add_header X-Frame-Options DENY always;\n",
+        "add_trailer Server-Timing $request_time;\n",
+        "proxy_set_header X-Real-IP $remote_addr;\n",
+        "fastcgi_param HTTPS $https if_not_empty;\n",
+    ] {
+        let dir = parse_directives(text).unwrap().pop().unwrap();
+        assert_eq!(&dir.to_string(), text);
+    }
+}
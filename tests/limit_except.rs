@@ -0,0 +1,46 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+use nginx_config::ast::{Item, LimitExcept, AccessRule, Source};
+
+
+fn limit_except(text: &str) -> LimitExcept {
+    let ast = parse_main(text).unwrap();
+    let found = ast.all_directives().filter_map(|dir| match dir.item {
+        Item::LimitExcept(ref lim) => Some(lim.clone()),
+        _ => None,
+    }).next();
+    found.expect("no limit_except")
+}
+
+#[test]
+fn methods() {
+    let lim = limit_except("limit_except GET POST { deny all; }");
+    assert_eq!(lim.methods, vec!["GET", "POST"]);
+}
+
+#[test]
+fn access_rules() {
+    let lim = limit_except(r#"
+        location /upload {
+            limit_except GET {
+                allow 192.168.1.0/24;
+                allow 10.0.0.1;
+                deny all;
+            }
+        }
+    "#);
+    assert_eq!(lim.methods, vec!["GET"]);
+    assert_eq!(lim.access_rules(), vec![
+        AccessRule::Allow(
+            &Source::Network("192.168.1.0".parse().unwrap(), 24)),
+        AccessRule::Allow(&Source::Ip("10.0.0.1".parse().unwrap())),
+        AccessRule::Deny(&Source::All),
+    ]);
+}
+
+#[test]
+fn needs_methods() {
+    assert!(parse_main("limit_except { deny all; }").is_err());
+}
@@ -0,0 +1,136 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::{parse_main, parse_main_with, ParseOptions};
+use nginx_config::ast::{Item, Listen, Address, HttpExt};
+
+
+fn listen(text: &str) -> Listen {
+    match parse_main(text).unwrap().directives.remove(0).item {
+        Item::Listen(lst) => lst,
+        item => panic!("not a listen: {:?}", item),
+    }
+}
+
+fn permutations(items: &[&'static str]) -> Vec<Vec<&'static str>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+    let mut result = Vec::new();
+    for (idx, item) in items.iter().enumerate() {
+        let mut rest = items.to_vec();
+        rest.remove(idx);
+        for mut perm in permutations(&rest) {
+            perm.insert(0, item);
+            result.push(perm);
+        }
+    }
+    result
+}
+
+#[test]
+fn ipv6_ssl_default_server() {
+    let lst = listen("listen [::]:443 ssl default_server;");
+    assert_eq!(lst.address, Address::Ip("[::]:443".parse().unwrap()));
+    assert!(lst.ssl);
+    assert!(lst.default_server);
+}
+
+#[test]
+fn option_order() {
+    let mut expected = Listen::new(Address::Ip("[::]:443".parse().unwrap()));
+    expected.ssl = true;
+    expected.default_server = true;
+    expected.ext = Some(HttpExt::Http2);
+    expected.reuseport = true;
+    expected.backlog = Some(1024);
+    let options = ["ssl", "default_server", "http2", "reuseport",
+                   "backlog=1024"];
+    for perm in permutations(&options) {
+        let text = format!("listen [::]:443 {};", perm.join(" "));
+        assert_eq!(listen(&text), expected, "{}", text);
+    }
+}
+
+#[test]
+fn address_first() {
+    assert!(parse_main("listen ssl [::]:443;").is_err());
+    assert!(parse_main("listen default_server 80;").is_err());
+}
+
+#[test]
+fn udp() {
+    let lst = listen("listen 53 udp;");
+    assert_eq!(lst.address, Address::Port(53));
+    assert!(lst.udp);
+    assert!(!lst.reuseport);
+    let lst = listen("listen 127.0.0.1:53 udp reuseport;");
+    assert!(lst.udp);
+    assert!(lst.reuseport);
+    assert_eq!(Item::Listen(lst).to_string(),
+               "listen 127.0.0.1:53 udp reuseport;\n");
+    assert!(!listen("listen 53;").udp);
+}
+
+#[test]
+fn multipath() {
+    let lst = listen("listen 443 ssl multipath;");
+    assert!(lst.multipath);
+    assert!(lst.extra.is_empty());
+}
+
+#[test]
+fn unknown_options() {
+    let text = "listen 80 somenewoption=1 reuseport other;\n";
+    let ast = parse_main_with(text, &ParseOptions::default()).unwrap();
+    match ast.directives[0].item {
+        Item::Listen(ref lst) => {
+            assert!(lst.reuseport);
+            assert_eq!(lst.extra, vec!["somenewoption=1", "other"]);
+        }
+        ref item => panic!("not a listen: {:?}", item),
+    }
+    assert_eq!(ast.to_string(), "listen 80 reuseport somenewoption=1 other;\n");
+    assert!(parse_main(text).is_err());
+    assert!(parse_main_with(text, ParseOptions::default().strict(true))
+        .is_err());
+    let text = "listen 80 somenewoption=1;\n";
+    let ast = parse_main_with(text, &ParseOptions::default()).unwrap();
+    assert_eq!(ast.to_string(), text);
+    assert!(parse_main(text).is_err());
+    assert!(parse_main_with(text, ParseOptions::default().strict(true))
+        .is_err());
+}
+
+#[test]
+fn buffer_sizes() {
+    let lst = listen("listen 80 rcvbuf=64k sndbuf=1m;");
+    assert_eq!(lst.rcvbuf, Some(64 << 10));
+    assert_eq!(lst.sndbuf, Some(1 << 20));
+    assert_eq!(listen("listen 80 rcvbuf=1000;").rcvbuf, Some(1000));
+    assert!(parse_main("listen 80 rcvbuf=lots;").is_err());
+}
+
+#[test]
+fn mixed_options_roundtrip() {
+    let text = "\
+server {
+    listen [::]:443 default_server so_keepalive=on ssl http2 quic \
+        backlog=1024 \"accept_filter=dataready\" rcvbuf=64k reuseport;
+}
+";
+    let printed = "\
+server {
+    listen [::]:443 default_server ssl http2 backlog=1024 rcvbuf=64k \
+        reuseport so_keepalive=on quic \"accept_filter=dataready\";
+}
+";
+    let ast = parse_main_with(text, &ParseOptions::default()).unwrap();
+    // unknown options are moved after the known ones
+    assert_eq!(ast.to_string(), printed);
+    let again = parse_main_with(printed, &ParseOptions::default()).unwrap();
+    assert_eq!(again.to_string(), printed);
+    let mut normalized = ast.clone();
+    normalized.normalize();
+    assert_eq!(normalized, ast);
+}
@@ -0,0 +1,117 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+use nginx_config::ast::{Item, Location, LocationPattern};
+
+fn pattern(text: &str) -> LocationPattern {
+    let ast = parse_main(text).unwrap();
+    match ast.directives[0].item {
+        Item::Location(ref loc) => loc.pattern.clone(),
+        ref item => panic!("expected location, got {:?}", item),
+    }
+}
+
+fn roundtrip(value: &str) {
+    let ast = parse_main(value).unwrap();
+    assert_eq!(ast.to_string(), value);
+}
+
+#[test]
+fn named() {
+    assert_eq!(pattern("location @app { proxy_pass http://app; }"),
+               LocationPattern::Named("app".into()));
+}
+
+#[test]
+fn prefix() {
+    assert_eq!(pattern("location /app { proxy_pass http://app; }"),
+               LocationPattern::Prefix("/app".into()));
+}
+
+fn location(text: &str) -> Location {
+    match parse_main(text).unwrap().directives.remove(0).item {
+        Item::Location(loc) => loc,
+        item => panic!("expected location, got {:?}", item),
+    }
+}
+
+#[test]
+fn modifiers() {
+    let cases = [
+        ("location /app { }", false, "/app"),
+        ("location = /app { }", false, "/app"),
+        ("location ^~ /static/ { }", false, "/static/"),
+        ("location ~ \\.php$ { }", true, "\\.php$"),
+        ("location ~* \\.(gif|jpg)$ { }", true, "\\.(gif|jpg)$"),
+        ("location @fallback { }", false, "fallback"),
+    ];
+    for &(text, is_regex, pattern) in &cases {
+        let loc = location(text);
+        assert_eq!(loc.is_regex(), is_regex, "{}", text);
+        assert_eq!(loc.pattern.text(), pattern, "{}", text);
+    }
+}
+
+#[test]
+fn named_roundtrip() {
+    roundtrip("location @app {\n    proxy_pass http://app;\n}\n");
+}
+
+#[test]
+fn access_rules() {
+    use nginx_config::ast::{AccessRule, Source};
+    let ast = parse_main(r#"
+        location /admin {
+            allow 10.0.0.0/8;
+            allow 192.168.1.1;
+            deny all;
+            location /admin/public {
+                allow all;
+            }
+        }
+    "#).unwrap();
+    let loc = match ast.directives[0].item {
+        Item::Location(ref loc) => loc,
+        _ => unreachable!(),
+    };
+    assert_eq!(loc.access_rules(), vec![
+        AccessRule::Allow(&Source::Network("10.0.0.0".parse().unwrap(), 8)),
+        AccessRule::Allow(&Source::Ip("192.168.1.1".parse().unwrap())),
+        AccessRule::Deny(&Source::All),
+    ]);
+}
+
+#[test]
+fn regex_dollar_is_literal() {
+    assert_eq!(pattern("location ~ \\.php$ { }"),
+               LocationPattern::Regex(r"\.php$".into()));
+    assert_eq!(pattern(r#"location ~* "^/[a-z]{2}/(.*)$" { }"#),
+               LocationPattern::RegexInsensitive("^/[a-z]{2}/(.*)$".into()));
+}
+
+#[test]
+fn regex_with_both_quotes() {
+    roundtrip("location ~ \"a'b\\\"\" {\n}\n");
+    let ast = parse_main(r#"location ~ 'a"b\'c' { }"#).unwrap();
+    assert_eq!(ast.to_string(), "location ~ \"a\\\"b\\'c\" {\n}\n");
+    roundtrip(&ast.to_string());
+}
+
+#[test]
+fn map_regex_dollar_is_literal() {
+    use nginx_config::ast::MapPattern;
+    let ast = parse_main(r#"
+        map $uri $new {
+            "~^/a{2}/(.*)$" /b/$1;
+        }
+    "#).unwrap();
+    let map = match ast.directives[0].item {
+        Item::Map(ref map) => map,
+        _ => unreachable!(),
+    };
+    assert_eq!(map.patterns, vec![(
+        MapPattern::Regex("^/a{2}/(.*)$".into()),
+        "/b/$1".parse().unwrap(),
+    )]);
+}
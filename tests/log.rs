@@ -0,0 +1,61 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+use nginx_config::ast::{Item, LogFormat};
+
+
+fn log_format(text: &str) -> LogFormat {
+    match parse_main(text).unwrap().directives.remove(0).item {
+        Item::LogFormat(lf) => lf,
+        item => panic!("not a log format: {:?}", item),
+    }
+}
+
+#[test]
+fn multi_line() {
+    let lf = log_format(r#"
+        log_format main '$remote_addr - $remote_user [$time_local] '
+                        '"$request" $status $body_bytes_sent';
+    "#);
+    assert_eq!(lf.name, "main");
+    assert_eq!(lf.escape, None);
+    assert_eq!(lf.template, concat!(
+        r#"'$remote_addr - $remote_user [$time_local] "#,
+        r#""$request" $status $body_bytes_sent'"#,
+    ).parse().unwrap());
+}
+
+#[test]
+fn escape() {
+    let lf = log_format("log_format js escape=json '$uri';");
+    assert_eq!(lf.name, "js");
+    assert_eq!(lf.escape, Some("json".to_string()));
+    assert_eq!(lf.template, "$uri".parse().unwrap());
+}
+
+#[test]
+fn flags() {
+    let items = parse_main("log_not_found off; rewrite_log on;").unwrap()
+        .directives.into_iter().map(|d| d.item).collect::<Vec<_>>();
+    assert_eq!(items, vec![Item::LogNotFound(false), Item::RewriteLog(true)]);
+}
+
+#[test]
+fn invalid_flags() {
+    assert!(parse_main("log_not_found yes;").is_err());
+    assert!(parse_main("rewrite_log 1;").is_err());
+    assert!(parse_main("rewrite_log;").is_err());
+}
+
+#[test]
+fn quotes_roundtrip() {
+    let text = "log_format main '$remote_addr \"$request\"';\n";
+    let printed = parse_main(text).unwrap().to_string();
+    assert_eq!(printed, text);
+    let text = "log_format main '$remote_addr \"$request\" it\\'s';\n";
+    let printed = parse_main(text).unwrap().to_string();
+    assert_eq!(printed,
+        "log_format main \"$remote_addr \\\"$request\\\" it\\'s\";\n");
+    assert_eq!(parse_main(&printed).unwrap().to_string(), printed);
+}
@@ -0,0 +1,28 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_directives;
+use nginx_config::ast::{Item, Map};
+
+
+fn map(text: &str) -> Map {
+    let mut dirs = parse_directives(text).unwrap();
+    match dirs.pop().unwrap().item {
+        Item::Map(map) => map,
+        item => panic!("not a map: {:?}", item),
+    }
+}
+
+#[test]
+fn include_and_default() {
+    let map = map(r#"
+        map $host $upstream {
+            include conf/maps/foo.map;
+            default backend;
+            example.org static;
+        }
+    "#);
+    assert_eq!(map.includes, vec!["conf/maps/foo.map"]);
+    assert_eq!(map.default.unwrap().to_string(), "backend");
+    assert_eq!(map.patterns.len(), 1);
+}
@@ -0,0 +1,45 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+
+
+fn normalize(text: &str) -> String {
+    let mut ast = parse_main(text).unwrap();
+    ast.normalize();
+    ast.to_string()
+}
+
+#[test]
+fn listen_any_address() {
+    assert_eq!(normalize("listen *:80;"), "listen 80;\n");
+    assert_eq!(normalize("listen 0.0.0.0:8080 ssl;"), "listen 8080 ssl;\n");
+}
+
+#[test]
+fn listen_kept() {
+    assert_eq!(normalize("listen 80;"), "listen 80;\n");
+    assert_eq!(normalize("listen 127.0.0.1:80;"), "listen 127.0.0.1:80;\n");
+    assert_eq!(normalize("listen [::]:80;"), "listen [::]:80;\n");
+}
+
+#[test]
+fn listen_duplicate_flags() {
+    assert_eq!(normalize("listen *:443 ssl ssl;"), "listen 443 ssl;\n");
+}
+
+#[test]
+fn nested() {
+    assert_eq!(normalize("http { server { listen *:80; } }"),
+               "http {\n    server {\n        listen 80;\n    }\n}\n");
+}
+
+#[test]
+fn header_names() {
+    assert_eq!(normalize("proxy_hide_header X-Powered-By;"),
+               "proxy_hide_header x-powered-by;\n");
+    assert_eq!(normalize("proxy_pass_header Server;"),
+               "proxy_pass_header server;\n");
+    assert_eq!(normalize("add_header X-Frame-Options DENY;"),
+               "add_header X-Frame-Options DENY;\n");
+}
@@ -0,0 +1,52 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::time::Duration;
+
+use nginx_config::parse_directives;
+use nginx_config::ast::{Item, OpenFileCache};
+
+
+fn item(text: &str) -> Item {
+    parse_directives(text).unwrap().pop().unwrap().item
+}
+
+#[test]
+fn off() {
+    assert_eq!(item("open_file_cache off;"),
+        Item::OpenFileCache(OpenFileCache::Off));
+}
+
+#[test]
+fn max_inactive() {
+    assert_eq!(item("open_file_cache max=1000 inactive=20s;"),
+        Item::OpenFileCache(OpenFileCache::Cache {
+            max: 1000,
+            inactive: Some(Duration::from_secs(20)),
+        }));
+    assert_eq!(item("open_file_cache max=1000;"),
+        Item::OpenFileCache(OpenFileCache::Cache {
+            max: 1000,
+            inactive: None,
+        }));
+}
+
+#[test]
+fn any_order() {
+    assert_eq!(item("open_file_cache inactive=20s max=1000;"),
+        Item::OpenFileCache(OpenFileCache::Cache {
+            max: 1000,
+            inactive: Some(Duration::from_secs(20)),
+        }));
+}
+
+#[test]
+fn invalid() {
+    assert!(parse_directives("open_file_cache;").is_err());
+    assert!(parse_directives("open_file_cache max=lots;").is_err());
+    assert!(parse_directives("open_file_cache inactive=20s;").is_err());
+    assert!(parse_directives("open_file_cache max=10 inactive=x;").is_err());
+    assert!(parse_directives("open_file_cache max=1 max=2;").is_err());
+    assert!(parse_directives(
+        "open_file_cache inactive=1s max=1 inactive=2s;").is_err());
+}
@@ -0,0 +1,235 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::{parse_main, parse_main_with, ParseOptions, Pos};
+use nginx_config::ast::Item;
+
+
+const UNKNOWN: &str = "\
+http {
+    server {
+        listen 80;
+        brotli_types text/plain text/css;
+
+        vhost_traffic_status_filter_by_set_key $uri uri::$server_name {
+            return 404;
+        }
+    }
+}
+";
+
+#[test]
+fn lenient_accepts_unknown() {
+    let ast = parse_main_with(UNKNOWN, &ParseOptions::default()).unwrap();
+    let names = ast.all_directives()
+        .filter(|d| matches!(d.item, Item::Generic(..)))
+        .map(|d| d.item.keyword())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![
+        "brotli_types",
+        "vhost_traffic_status_filter_by_set_key",
+    ]);
+    assert_eq!(ast.to_string(), UNKNOWN);
+}
+
+#[test]
+fn strict_rejects_unknown() {
+    let err = parse_main_with(UNKNOWN, ParseOptions::default().strict(true))
+        .unwrap_err();
+    assert!(err.to_string().contains("Parse error at 4:9\n"), "{}", err);
+    assert!(parse_main(UNKNOWN).is_err());
+}
+
+#[test]
+fn lenient_validates_known() {
+    let opts = ParseOptions::default();
+    assert!(parse_main_with("gzip maybe;", &opts).is_err());
+    assert!(parse_main_with("server { brotli on }", &opts).is_err());
+}
+
+#[test]
+fn generic_position() {
+    let ast = parse_main_with("brotli on;", &ParseOptions::default())
+        .unwrap();
+    assert_eq!(ast.directives[0].position, Pos { line: 1, column: 1 });
+}
+
+#[test]
+fn generic_quoted_args() {
+    let text = "more_set_headers \"Server: nginx\" 'X-Frame-Options: DENY';\n";
+    let ast = parse_main_with(text, &ParseOptions::default()).unwrap();
+    match ast.directives[0].item {
+        Item::Generic(ref g) => {
+            assert_eq!(g.name, "more_set_headers");
+            let args = g.args.iter().map(|v| v.to_raw_string())
+                .collect::<Vec<_>>();
+            assert_eq!(args, vec!["Server: nginx", "X-Frame-Options: DENY"]);
+            assert!(g.block.is_none());
+        }
+        ref item => panic!("not a generic directive: {:?}", item),
+    }
+    assert_eq!(ast.to_string(),
+        "more_set_headers \"Server: nginx\" \"X-Frame-Options: DENY\";\n");
+}
+
+#[test]
+fn comments() {
+    let ast = parse_main_with("\
+        # listen 8080;\n\
+        # the main server\n\
+        \n\
+        server {\n\
+            listen 80; # no ssl yet\n\
+        }\n", &ParseOptions::default()).unwrap();
+    let comments = ast.all_directives()
+        .filter_map(|d| match d.item {
+            Item::Comment(ref c) => Some((&c.text[..], c.looks_like_directive)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(comments, vec![
+        (" listen 8080;", true),
+        (" the main server", false),
+    ]);
+    let trailing = ast.all_directives()
+        .filter_map(|d| d.trailing_comment.as_ref())
+        .collect::<Vec<_>>();
+    assert_eq!(trailing, vec![" no ssl yet"]);
+}
+
+#[test]
+fn comments_inside_directive() {
+    let opts = ParseOptions::default();
+    let ast = parse_main_with("\
+        gzip_types # comment\n  text/css;\n\
+        map $host $x {\n  # comment\n  default 1;\n}\n", &opts).unwrap();
+    assert_eq!(ast.directives.len(), 2);
+    assert!(parse_main_with("# only a comment\n", &opts).is_ok());
+    assert_eq!(parse_main("# comment\ngzip on;").unwrap().directives.len(), 1);
+}
+
+#[test]
+fn leading_comments() {
+    let mut ast = parse_main_with("\
+        # compress\n\
+        # everything\n\
+        gzip on;\n\
+        # listen 8080;\n\
+        # port\n\
+        listen 80; # not attached\n\
+        \n\
+        # frontend\n\
+        server {\n\
+        }\n", &ParseOptions::default()).unwrap();
+    let comments = ast.directives.iter()
+        .map(|d| d.leading_comments.join("|"))
+        .collect::<Vec<_>>();
+    assert_eq!(comments, vec![
+        " compress| everything", "", " port", " frontend",
+    ]);
+    ast.directives.swap(0, 2);
+    assert_eq!(ast.to_string(), "\
+        # port\n\
+        listen 80; # not attached\n\
+        # listen 8080;\n\
+        # compress\n\
+        # everything\n\
+        gzip on;\n\
+        \n\
+        # frontend\n\
+        server {\n\
+        }\n");
+}
+
+#[test]
+fn trailing_comments() {
+    let text = "\
+        listen 80; # main port\n\
+        # the server\n\
+        server { root /www; } # one line\n\
+        \n\
+        location / {\n\
+            root /www; #static\n\
+        } # after block\n\
+        # next line\n";
+    let ast = parse_main_with(text, &ParseOptions::default()).unwrap();
+    assert_eq!(ast.to_string(), "\
+        listen 80; # main port\n\
+        \n\
+        # the server\n\
+        server {\n\
+        \x20   root /www;\n\
+        } # one line\n\
+        \n\
+        location / {\n\
+        \x20   root /www; #static\n\
+        } # after block\n\
+        # next line\n");
+    let formatted = ast.to_string();
+    let again = parse_main_with(&formatted, &ParseOptions::default()).unwrap();
+    assert_eq!(again.to_string(), formatted);
+    let strict = parse_main(text).unwrap();
+    assert!(strict.directives.iter().all(|d| d.trailing_comment.is_none()));
+}
+
+#[test]
+fn comments_in_special_blocks() {
+    let opts = ParseOptions::default();
+    let ast = parse_main_with("\
+        map $a $b {\n default 0; # zero\n # own line\n x 1;\n}\n\
+        types {\n # html\n text/html html; # pages\n}\n\
+        upstream u {\n server a; # main\n}\n", &opts).unwrap();
+    // dropped in map and types, kept in upstream
+    let comments = ast.all_directives()
+        .filter_map(|d| match d.item {
+            Item::Comment(ref c) => Some(&c.text[..]),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(comments, vec![" main"]);
+    assert_eq!(ast.to_string(), "\
+        map $a $b {\n    default 0;\n    x 1;\n}\n\
+        \n\
+        types {\n    text/html html;\n}\n\
+        \n\
+        upstream u {\n    server a;\n    # main\n}\n");
+}
+
+#[test]
+fn generic_regex_args() {
+    let text = "proxy_cookie_path ~^/(.*)$ /$1;\n\
+                proxy_cookie_path \"~*^/a{2}/(.*)$\" /b/$1;\n";
+    let ast = parse_main_with(text, &ParseOptions::default()).unwrap();
+    match ast.directives[0].item {
+        Item::Generic(ref g) => {
+            assert_eq!(g.args[0].to_raw_string(), "~^/(.*)$");
+            assert_eq!(g.args[0].variables().count(), 0);
+            assert_eq!(g.args[1].variables().collect::<Vec<_>>(), vec!["1"]);
+        }
+        ref item => panic!("not a generic directive: {:?}", item),
+    }
+    assert_eq!(ast.to_string(), text);
+    assert_eq!(ast.directives[0].item.keyword(), "proxy_cookie_path");
+    assert_eq!(ast.directives[0].item.directive_name(), "");
+}
+
+#[test]
+fn commented_out_directives() {
+    let ast = parse_main_with("\
+        # listen 8080;\n\
+        # server {\n\
+        # location / { root /x; }\n\
+        # proxy_pass http://app;\n\
+        # the main server\n\
+        # TODO: fix;\n\
+        # 10 workers;\n\
+        # gzip: on;\n", &ParseOptions::default()).unwrap();
+    let flags = ast.all_directives()
+        .filter_map(|d| match d.item {
+            Item::Comment(ref c) => Some(c.looks_like_directive),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(flags, vec![true, true, true, true,
+                           false, false, false, false]);
+}
@@ -0,0 +1,27 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::{parse_main, Pos};
+
+
+#[test]
+fn accessors() {
+    let pos = Pos { line: 3, column: 17 };
+    assert_eq!(pos.line(), 3);
+    assert_eq!(pos.column(), 17);
+}
+
+#[test]
+fn display() {
+    assert_eq!(Pos { line: 3, column: 17 }.to_string(), "3:17");
+    assert_eq!(format!("{:?}", Pos { line: 1, column: 1 }), "Pos(1:1)");
+}
+
+#[test]
+fn directive_positions() {
+    let ast = parse_main("daemon on;\nhttp {\n    gzip on;\n}\n").unwrap();
+    let positions = ast.all_directives()
+        .map(|d| d.position.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(positions, vec!["1:1", "2:1", "3:5"]);
+}
@@ -0,0 +1,164 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::time::Duration;
+
+use nginx_config::parse_main;
+use nginx_config::ast::{Item, ProxyCacheValid, ProxyHttpVersion};
+use nginx_config::ast::{ProxyPass, ProxyTarget};
+
+
+fn item(text: &str) -> Item {
+    parse_main(text).unwrap().directives.remove(0).item
+}
+
+#[test]
+fn read_timeout() {
+    assert_eq!(item("proxy_read_timeout 300s;"),
+               Item::ProxyReadTimeout(Duration::from_secs(300)));
+    assert_eq!(item("proxy_read_timeout 1h;"),
+               Item::ProxyReadTimeout(Duration::from_secs(3600)));
+    assert_eq!(item("proxy_read_timeout 60;"),
+               Item::ProxyReadTimeout(Duration::from_secs(60)));
+}
+
+#[test]
+fn http_version() {
+    assert_eq!(item("proxy_http_version 1.0;"),
+               Item::ProxyHttpVersion(ProxyHttpVersion::V1_0));
+    assert_eq!(item("proxy_http_version 1.1;"),
+               Item::ProxyHttpVersion(ProxyHttpVersion::V1_1));
+    let err = parse_main("proxy_http_version 2.0;").unwrap_err();
+    assert!(err.to_string().contains("invalid http version"));
+}
+
+#[test]
+fn compound_timeouts() {
+    assert_eq!(item("proxy_connect_timeout 1m30s;"),
+               Item::ProxyConnectTimeout(Duration::from_secs(90)));
+    assert_eq!(item("proxy_send_timeout 1s500ms;"),
+               Item::ProxySendTimeout(Duration::from_millis(1500)));
+}
+
+#[test]
+fn invalid_timeouts() {
+    assert!(parse_main("proxy_read_timeout 5x;").is_err());
+    assert!(parse_main("proxy_read_timeout 1s1h;").is_err());
+    assert!(parse_main("proxy_read_timeout h;").is_err());
+    assert!(parse_main("proxy_read_timeout 5é;").is_err());
+}
+
+#[test]
+fn cache_valid() {
+    assert_eq!(item("proxy_cache_valid 200 302 10m;"),
+               Item::ProxyCacheValid(ProxyCacheValid::Specific(
+                   vec![200, 302], Duration::from_secs(600))));
+    assert_eq!(item("proxy_cache_valid any 1m;"),
+               Item::ProxyCacheValid(ProxyCacheValid::Any(
+                   Duration::from_secs(60))));
+    assert_eq!(item("proxy_cache_valid 5m;"),
+               Item::ProxyCacheValid(ProxyCacheValid::Normal(
+                   Duration::from_secs(300))));
+}
+
+#[test]
+fn invalid_cache_valid() {
+    assert!(parse_main("proxy_cache_valid 200 10x;").is_err());
+    assert!(parse_main("proxy_cache_valid 200 any 1m;").is_err());
+    assert!(parse_main("proxy_cache_valid 999 1m;").is_err());
+}
+
+#[test]
+fn cache_and_buffering() {
+    assert_eq!(item("proxy_buffering off;"), Item::ProxyBuffering(false));
+    assert_eq!(item("proxy_cache my_zone;"),
+               Item::ProxyCache("my_zone".parse().unwrap()));
+}
+
+fn has_uri(text: &str) -> bool {
+    match item(text) {
+        Item::ProxyPass(ref p) => p.has_uri_component(),
+        item => panic!("not a proxy_pass: {:?}", item),
+    }
+}
+
+#[test]
+fn proxy_pass_uri_component() {
+    assert!(!has_uri("proxy_pass http://app;"));
+    assert!(has_uri("proxy_pass http://app/;"));
+    assert!(has_uri("proxy_pass http://app:8080/api/;"));
+    assert!(!has_uri("proxy_pass http://$backend;"));
+    assert!(has_uri("proxy_pass http://$backend/;"));
+    assert!(!has_uri("proxy_pass http://unix:/tmp/app.sock;"));
+    assert!(has_uri("proxy_pass http://unix:/tmp/app.sock:/;"));
+}
+
+fn proxy_pass(text: &str) -> ProxyPass {
+    match item(text) {
+        Item::ProxyPass(p) => p,
+        item => panic!("not a proxy_pass: {:?}", item),
+    }
+}
+
+#[test]
+fn proxy_pass_unix_socket() {
+    let text = "proxy_pass http://unix:/var/run/app.sock:/api/;";
+    let pass = proxy_pass(text);
+    assert_eq!(pass.target(),
+        Some(ProxyTarget::Unix("/var/run/app.sock".to_string())));
+    assert!(pass.is_unix_socket());
+    assert_eq!(pass.uri().as_ref().map(|u| &u[..]), Some("/api/"));
+    assert_eq!(parse_main(text).unwrap().to_string(),
+        format!("{}\n", text));
+    let pass = proxy_pass("proxy_pass http://unix:/tmp/app.sock;");
+    assert_eq!(pass.target(),
+        Some(ProxyTarget::Unix("/tmp/app.sock".to_string())));
+    assert_eq!(pass.uri(), None);
+}
+
+#[test]
+fn proxy_pass_target() {
+    let pass = proxy_pass("proxy_pass http://app:8080/v1$request_uri;");
+    assert_eq!(pass.target(),
+        Some(ProxyTarget::Host("app:8080".to_string())));
+    assert!(!pass.is_unix_socket());
+    assert_eq!(pass.uri().as_ref().map(|u| &u[..]), Some("/v1$request_uri"));
+    assert_eq!(proxy_pass("proxy_pass http://$backend/;").target(), None);
+    assert_eq!(proxy_pass("proxy_pass $backend;").target(), None);
+}
+
+#[test]
+fn proxy_redirect() {
+    use nginx_config::ast::ProxyRedirect;
+    assert_eq!(item("proxy_redirect default;"),
+               Item::ProxyRedirect(ProxyRedirect::Default));
+    assert_eq!(item("proxy_redirect off;"),
+               Item::ProxyRedirect(ProxyRedirect::Off));
+    assert_eq!(item("proxy_redirect http://localhost:8000/ /;"),
+               Item::ProxyRedirect(ProxyRedirect::Redirect {
+                   redirect: "http://localhost:8000/".parse().unwrap(),
+                   replacement: "/".parse().unwrap(),
+               }));
+    assert!(parse_main("proxy_redirect http://localhost:8000/;").is_err());
+}
+
+#[test]
+fn proxy_redirect_regex() {
+    use nginx_config::ast::ProxyRedirect;
+    let text = "proxy_redirect ~^/u/([^/]+)$ http://$1.example.com/;";
+    assert_eq!(item(text), Item::ProxyRedirect(ProxyRedirect::Regex {
+        pattern: "^/u/([^/]+)$".to_string(),
+        case_insensitive: false,
+        replacement: "http://$1.example.com/".parse().unwrap(),
+    }));
+    assert_eq!(item("proxy_redirect ~*/user/([^/]+)/(.+)$ /$2;"),
+        Item::ProxyRedirect(ProxyRedirect::Regex {
+            pattern: "/user/([^/]+)/(.+)$".to_string(),
+            case_insensitive: true,
+            replacement: "/$2".parse().unwrap(),
+        }));
+    assert_eq!(parse_main(text).unwrap().to_string(), format!("{}\n", text));
+    let quoted = r#"proxy_redirect "~^/a{2}$" /;"#;
+    assert_eq!(parse_main(quoted).unwrap().to_string(),
+        format!("{}\n", quoted));
+}
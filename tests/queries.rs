@@ -0,0 +1,347 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::{parse_main, parse_main_with, ParseOptions};
+use nginx_config::ast::{Item, RealIpFrom, EffectiveListens};
+
+
+#[test]
+fn listens() {
+    let ast = parse_main(r#"
+        http {
+            server {
+                listen 80;
+            }
+            server {
+                listen 127.0.0.1:8080;
+            }
+        }
+        server {
+            listen [::]:443 ssl;
+        }
+    "#).unwrap();
+    let listens = ast.listens().iter().map(|l| l.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(listens, vec![
+        "listen 80;\n",
+        "listen 127.0.0.1:8080;\n",
+        "listen [::]:443 ssl;\n",
+    ]);
+}
+
+#[test]
+fn real_ip_sources() {
+    let ast = parse_main(r#"
+        set_real_ip_from 10.0.0.0/8;
+        http {
+            real_ip_header X-Forwarded-For;
+            server {
+                set_real_ip_from 192.168.1.1;
+                set_real_ip_from unix:;
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.real_ip_sources(), vec![
+        &RealIpFrom::Network("10.0.0.0".parse().unwrap(), 8),
+        &RealIpFrom::Ip("192.168.1.1".parse().unwrap()),
+        &RealIpFrom::Unix,
+    ]);
+    let header = ast.all_directives().filter_map(|d| match d.item {
+        Item::RealIpHeader(ref h) => Some(h.to_string()),
+        _ => None,
+    }).collect::<Vec<_>>();
+    assert_eq!(header, vec!["X-Forwarded-For"]);
+}
+
+#[test]
+fn variables_defined() {
+    let ast = parse_main_with(r#"
+        http {
+            map $http_upgrade $connection_upgrade {
+                default upgrade;
+            }
+            geo $remote_addr $internal {
+                default 0;
+                10.0.0.0/8 1;
+            }
+            server {
+                set $foo bar;
+                location / {
+                    if ($internal) {
+                        set $backend internal;
+                    }
+                }
+            }
+        }
+    "#, &ParseOptions::default()).unwrap();
+    let mut vars = ast.variables_defined().into_iter().collect::<Vec<_>>();
+    vars.sort();
+    assert_eq!(vars, vec!["backend", "connection_upgrade", "foo", "internal"]);
+}
+
+#[test]
+fn print_one_server() {
+    let ast = parse_main(r#"
+        http {
+            gzip on;
+            server {
+                listen 80;
+                server_name a.example.com;
+            }
+            server {
+                listen 80;
+                server_name b.example.com;
+                location / {
+                    root /var/www/b;
+                }
+            }
+        }
+    "#).unwrap();
+    let server = ast.all_directives().filter_map(|d| match d.item {
+        Item::Server(ref s) => Some(s),
+        _ => None,
+    }).nth(1).unwrap();
+    assert_eq!(server.to_string(), "\
+server {
+    listen 80;
+    server_name b.example.com;
+
+    location / {
+        root /var/www/b;
+    }
+}
+");
+}
+
+#[test]
+fn has_directive() {
+    let ast = parse_main_with(r#"
+        http {
+            server {
+                location / {
+                    gzip on;
+                    brotli on;
+                }
+            }
+        }
+    "#, &ParseOptions::default()).unwrap();
+    assert!(ast.has_directive("gzip"));
+    assert!(ast.has_directive("location"));
+    assert!(ast.has_directive("brotli"));
+    assert!(!ast.has_directive("gzip_static"));
+}
+
+#[test]
+fn includes() {
+    let ast = parse_main(r#"
+        include mime.types;
+        http {
+            server {
+                location ~ \.php$ {
+                    include fastcgi_params;
+                    fastcgi_pass unix:/run/php/php-fpm.sock;
+                }
+                location / {
+                    if ($args) {
+                        include /etc/nginx/args/*.conf;
+                    }
+                }
+            }
+        }
+    "#).unwrap();
+    let includes = ast.includes().iter().map(|v| v.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(includes, vec![
+        "mime.types",
+        "fastcgi_params",
+        "/etc/nginx/args/*.conf",
+    ]);
+}
+
+#[test]
+fn effective_listens() {
+    let ast = parse_main(r#"
+        http {
+            server {
+                listen 443 ssl;
+                listen [::]:443 ssl;
+                server_name a.example.com;
+            }
+            server {
+                server_name b.example.com;
+            }
+        }
+    "#).unwrap();
+    let servers = ast.all_directives().filter_map(|dir| match dir.item {
+        Item::Server(ref s) => Some(s),
+        _ => None,
+    }).collect::<Vec<_>>();
+    match servers[0].effective_listens() {
+        EffectiveListens::Explicit(listens) => {
+            let listens = listens.iter().map(|l| l.to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(listens, vec![
+                "listen 443 ssl;\n",
+                "listen [::]:443 ssl;\n",
+            ]);
+        }
+        EffectiveListens::Default => panic!("server has listens"),
+    }
+    assert_eq!(servers[1].effective_listens(), EffectiveListens::Default);
+}
+
+#[test]
+fn server_listens() {
+    let ast = parse_main(r#"
+        server {
+            listen 80;
+            server_name example.com;
+            listen [::]:80;
+        }
+    "#).unwrap();
+    let server = match ast.directives[0].item {
+        Item::Server(ref s) => s,
+        ref item => panic!("not a server: {:?}", item),
+    };
+    let listens = server.listens().iter().map(|l| l.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(listens, vec!["listen 80;\n", "listen [::]:80;\n"]);
+}
+
+#[test]
+fn stats() {
+    use nginx_config::ast::ConfigStats;
+    let ast = parse_main(r#"
+        worker_processes auto;
+        http {
+            upstream app {
+                server 127.0.0.1:8080;
+            }
+            server {
+                listen 80;
+                location / {
+                    proxy_pass http://app;
+                }
+                location /static/ {
+                    root /var/www;
+                    location ~ \.css$ {
+                        expires 1d;
+                    }
+                }
+            }
+            server {
+                listen 8080;
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.stats(), ConfigStats {
+        servers: 2,
+        locations: 3,
+        upstreams: 1,
+        directives: 13,
+        max_depth: 4,
+    });
+    assert_eq!(parse_main("daemon on;").unwrap().stats().max_depth, 0);
+}
+
+#[test]
+fn stats_upstream() {
+    let ast = parse_main(r#"
+        http {
+            upstream app {
+                server 127.0.0.1:8080;
+                server 127.0.0.1:8081;
+                keepalive 16;
+            }
+        }
+    "#).unwrap();
+    let stats = ast.stats();
+    assert_eq!(stats.upstreams, 1);
+    assert_eq!(stats.servers, 0);
+    assert_eq!(stats.directives, 2);
+    assert_eq!(stats.max_depth, 2);
+}
+
+#[test]
+fn items() {
+    let ast = parse_main(r#"
+        worker_processes 2;
+        http {
+            server { listen 80; }
+        }
+        daemon off;
+    "#).unwrap();
+    let names = ast.items().map(|item| item.directive_name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["worker_processes", "http", "daemon"]);
+    let items = ast.into_items();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[2], Item::Daemon(false));
+}
+
+#[test]
+fn keyword() {
+    let ast = parse_main_with(r#"
+        http {
+            more_set_headers "Server: x";
+            gzip on;
+        }
+    "#, &ParseOptions::default()).unwrap();
+    let keywords = ast.all_directives().map(|d| d.item.keyword())
+        .collect::<Vec<_>>();
+    assert_eq!(keywords, vec!["http", "more_set_headers", "gzip"]);
+}
+
+#[test]
+fn keyword_of_every_variant() {
+    for entry in std::fs::read_dir("tests/configs").unwrap() {
+        let path = entry.unwrap().path();
+        // these are parsed by `parse_main_with` in roundtrips
+        if path.to_str().unwrap().ends_with("_lenient.conf") {
+            continue;
+        }
+        let text = std::fs::read_to_string(&path).unwrap();
+        let ast = parse_main(&text).unwrap();
+        for dir in ast.all_directives() {
+            let printed = dir.to_string();
+            let first = printed.split(|c: char| {
+                c.is_whitespace() || c == ';'
+            }).next().unwrap();
+            assert_eq!(dir.item.keyword(), first, "in {:?}", path);
+        }
+    }
+}
+
+#[test]
+fn locations() {
+    let ast = parse_main(r#"
+        http {
+            server {
+                server_name a.example.com;
+                location / {
+                    location /nested {
+                        return 404;
+                    }
+                }
+                location @app {
+                    proxy_pass http://app;
+                }
+            }
+            server {
+                server_name b.example.com;
+                location = /health {
+                    return 200;
+                }
+            }
+        }
+    "#).unwrap();
+    let table = ast.locations().iter()
+        .map(|l| (l.server, l.location.pattern.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(table, vec![
+        (0, "/".to_string()),
+        (0, "/nested".to_string()),
+        (0, "@app".to_string()),
+        (1, "= /health".to_string()),
+    ]);
+}
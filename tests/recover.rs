@@ -0,0 +1,87 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::{parse_main, parse_recover};
+
+
+fn recover(text: &str) -> (String, Vec<String>) {
+    let (ast, errors) = parse_recover(text);
+    (ast.to_string(), errors.iter().map(|e| e.to_string()).collect())
+}
+
+#[test]
+fn no_errors() {
+    let text = "http {\n    server {\n        listen 80;\n    }\n}\n";
+    let (ast, errors) = parse_recover(text);
+    assert_eq!(ast, parse_main(text).unwrap());
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn two_errors() {
+    let (ast, errors) = recover("\
+        http {
+            gzip maybe;
+            server {
+                listen 80;
+                root;
+                location / {
+                    return 204;
+                }
+            }
+            gzip_static on;
+        }
+    ");
+    assert_eq!(ast, "\
+http {
+    server {
+        listen 80;
+
+        location / {
+            return 204;
+        }
+    }
+    gzip_static on;
+}
+");
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].starts_with("parse error: Parse error at 2:18\n"),
+            "{}", errors[0]);
+    assert!(errors[1].starts_with("parse error: Parse error at 5:21\n"),
+            "{}", errors[1]);
+}
+
+#[test]
+fn skip_block() {
+    let (ast, errors) = recover("\
+        location = { return 204; }
+        location /a { return 200; }
+    ");
+    assert_eq!(ast, "location /a {\n    return 200;\n}\n");
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn missing_semicolon() {
+    let (ast, errors) = recover("server { listen 80 }\ndaemon on;");
+    assert_eq!(ast, "server {\n}\ndaemon on;\n");
+    assert_eq!(errors, vec![
+        "parse error: Parse error at 1:20\nUnexpected `}`\nExpected `;`\n",
+    ]);
+}
+
+#[test]
+fn stray_block_end() {
+    let (ast, errors) = recover("daemon on;\n}\ndaemon off;");
+    assert_eq!(ast, "daemon on;\ndaemon off;\n");
+    assert_eq!(errors, vec![
+        "parse error: Parse error at 2:1\nUnexpected `}`\n",
+    ]);
+}
+
+#[test]
+fn tokenizer_error() {
+    let (ast, errors) = recover("daemon on;\nroot \"/var/www;\n");
+    assert_eq!(ast, "daemon on;\n");
+    assert_eq!(errors.len(), 1);
+}
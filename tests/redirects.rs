@@ -0,0 +1,24 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_directives;
+use nginx_config::ast::Item;
+
+
+fn item(text: &str) -> Item {
+    parse_directives(text).unwrap().pop().unwrap().item
+}
+
+#[test]
+fn absolute_redirect() {
+    assert_eq!(item("absolute_redirect on;"), Item::AbsoluteRedirect(true));
+    assert_eq!(item("absolute_redirect off;"), Item::AbsoluteRedirect(false));
+    assert!(parse_directives("absolute_redirect;").is_err());
+}
+
+#[test]
+fn port_in_redirect() {
+    assert_eq!(item("port_in_redirect on;"), Item::PortInRedirect(true));
+    assert_eq!(item("port_in_redirect off;"), Item::PortInRedirect(false));
+    assert!(parse_directives("port_in_redirect 8080;").is_err());
+}
@@ -0,0 +1,28 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+use nginx_config::ast::{Item, Rewrite, RewriteFlag};
+
+
+fn rewrite(text: &str) -> Rewrite {
+    match parse_main(text).unwrap().directives.remove(0).item {
+        Item::Rewrite(rw) => rw,
+        item => panic!("not a rewrite: {:?}", item),
+    }
+}
+
+#[test]
+fn regex_is_literal() {
+    let rw = rewrite("rewrite ^/old/(.*)$ /new/$1 permanent;");
+    assert_eq!(rw.regex, "^/old/(.*)$");
+    assert_eq!(rw.replacement, "/new/$1".parse().unwrap());
+    assert_eq!(rw.flag, Some(RewriteFlag::Permanent));
+}
+
+#[test]
+fn quoted_regex() {
+    let rw = rewrite(r#"rewrite "^/(\d{4})/(.*)$" /$2?year=$1;"#);
+    assert_eq!(rw.regex, r"^/(\d{4})/(.*)$");
+    assert_eq!(rw.flag, None);
+}
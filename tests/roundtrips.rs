@@ -4,7 +4,7 @@ extern crate nginx_config;
 use std::io::Read;
 use std::fs::File;
 
-use nginx_config::parse_main;
+use nginx_config::{parse_main, parse_main_with, ParseOptions};
 
 fn roundtrip(filename: &str) {
     let mut buf = String::with_capacity(1024);
@@ -15,6 +15,16 @@ fn roundtrip(filename: &str) {
     assert_eq!(ast.to_string(), buf);
 }
 
+/// Same as `roundtrip` but keeps comments and unknown directives
+fn roundtrip_lenient(filename: &str) {
+    let mut buf = String::with_capacity(1024);
+    let path = format!("tests/configs/{}.conf", filename);
+    let mut f = File::open(&path).unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let ast = parse_main_with(&buf, &ParseOptions::default()).unwrap();
+    assert_eq!(ast.to_string(), buf);
+}
+
 #[test] fn minimal() { roundtrip("minimal"); }
 #[test] fn master_process() { roundtrip("master_process"); }
 #[test] fn worker_processes_auto() { roundtrip("worker_processes_auto"); }
@@ -53,7 +63,57 @@ fn roundtrip(filename: &str) {
 #[test] fn access_log() { roundtrip("access_log"); }
 #[test] fn limit_except() { roundtrip("limit_except"); }
 #[test] fn real_ip() { roundtrip("real_ip"); }
+#[test] fn charset() { roundtrip("charset"); }
+#[test] fn auth_basic() { roundtrip("auth_basic"); }
 #[test] fn error_log() { roundtrip("error_log"); }
 #[test] fn index() { roundtrip("index"); }
+#[test] fn upstream() { roundtrip("upstream"); }
+#[test] fn fastcgi() { roundtrip("fastcgi"); }
+#[test] fn log_flags() { roundtrip("log_flags"); }
+#[test] fn types() { roundtrip("types"); }
+#[test] fn ssl_session_cache() { roundtrip("ssl_session_cache"); }
+#[test] fn timeouts() { roundtrip("timeouts"); }
+#[test] fn stream() { roundtrip("stream"); }
+#[test] fn geoip() { roundtrip("geoip"); }
+#[test] fn merge_slashes() { roundtrip("merge_slashes"); }
+#[test] fn redirects() { roundtrip("redirects"); }
+#[test] fn open_file_cache() { roundtrip("open_file_cache"); }
+#[test] fn satisfy() { roundtrip("satisfy"); }
+#[test] fn if_quotes() { roundtrip("if_quotes"); }
+#[test] fn upstream_lenient() { roundtrip_lenient("upstream_lenient"); }
 // not working yet
 //#[test] fn few_locations() { roundtrip("few_locations"); }
+
+#[test]
+fn from_str() {
+    use nginx_config::ast::Main;
+
+    let mut buf = String::with_capacity(1024);
+    File::open("tests/configs/proxy.conf").unwrap()
+        .read_to_string(&mut buf).unwrap();
+    let ast: Main = buf.parse().unwrap();
+    let again: Main = ast.to_string().parse().unwrap();
+    assert_eq!(ast, again);
+}
+
+#[test]
+fn clone() {
+    for entry in std::fs::read_dir("tests/configs").unwrap() {
+        let mut buf = String::with_capacity(1024);
+        File::open(entry.unwrap().path()).unwrap()
+            .read_to_string(&mut buf).unwrap();
+        if let Ok(ast) = parse_main(&buf) {
+            let copy = ast.clone();
+            assert_eq!(format!("{:?}", copy), format!("{:?}", ast));
+            assert_eq!(copy, ast);
+        }
+    }
+}
+
+#[test]
+fn eq_ignores_position() {
+    let a = parse_main("server { listen 80; location / { root /x; } }");
+    let b = parse_main("\n  server {\n\tlisten  80;\n\n  location / {\n\
+                        root /x;\n}\n}\n");
+    assert_eq!(a.unwrap(), b.unwrap());
+}
@@ -0,0 +1,29 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_directives;
+use nginx_config::ast::{Item, ServerTokens};
+
+
+fn item(text: &str) -> Item {
+    parse_directives(text).unwrap().pop().unwrap().item
+}
+
+#[test]
+fn keywords() {
+    assert_eq!(item("server_tokens on;"),
+        Item::ServerTokens(ServerTokens::On));
+    assert_eq!(item("server_tokens off;"),
+        Item::ServerTokens(ServerTokens::Off));
+    assert_eq!(item("server_tokens build;"),
+        Item::ServerTokens(ServerTokens::Build));
+}
+
+#[test]
+fn custom() {
+    assert_eq!(item("server_tokens \"myserver/$hostname\";"),
+        Item::ServerTokens(ServerTokens::Custom(
+            "\"myserver/$hostname\"".parse().unwrap())));
+    assert!(parse_directives("server_tokens;").is_err());
+    assert!(parse_directives("server_tokens on off;").is_err());
+}
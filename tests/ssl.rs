@@ -0,0 +1,108 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::{parse_directives, parse_main_with, ParseOptions};
+use nginx_config::ast::{Item, SslSessionCache, SslSessionCacheKind};
+
+
+fn item(text: &str) -> Item {
+    parse_directives(text).unwrap().pop().unwrap().item
+}
+
+fn cache(text: &str) -> SslSessionCache {
+    match item(text) {
+        Item::SslSessionCache(cache) => cache,
+        item => panic!("not a ssl_session_cache: {:?}", item),
+    }
+}
+
+#[test]
+fn shared() {
+    assert_eq!(cache("ssl_session_cache shared:SSL:10m;"),
+        SslSessionCache::Caches(vec![SslSessionCacheKind::Shared {
+            name: "SSL".into(),
+            size: 10 << 20,
+        }]));
+}
+
+#[test]
+fn off_and_none() {
+    assert_eq!(cache("ssl_session_cache off;"), SslSessionCache::Off);
+    assert_eq!(cache("ssl_session_cache none;"), SslSessionCache::None);
+}
+
+#[test]
+fn builtin_and_shared() {
+    use nginx_config::ast::SslSessionCacheKind::*;
+    assert_eq!(cache("ssl_session_cache builtin;"),
+        SslSessionCache::Caches(vec![Builtin(None)]));
+    assert_eq!(cache("ssl_session_cache builtin:1000 shared:a:b:512k;"),
+        SslSessionCache::Caches(vec![
+            Builtin(Some(1000)),
+            Shared { name: "a:b".into(), size: 512 << 10 },
+        ]));
+}
+
+#[test]
+fn invalid() {
+    assert!(parse_directives("ssl_session_cache;").is_err());
+    assert!(parse_directives("ssl_session_cache shared:SSL;").is_err());
+    assert!(parse_directives("ssl_session_cache shared::1m;").is_err());
+    assert!(parse_directives("ssl_session_cache shared:SSL:10x;").is_err());
+    assert!(parse_directives("ssl_session_cache builtin:many;").is_err());
+    assert!(parse_directives("ssl_session_cache off shared:a:1m;").is_err());
+}
+
+#[test]
+fn dhparam() {
+    assert_eq!(item("ssl_dhparam /etc/nginx/dhparam.pem;"),
+        Item::SslDhparam("/etc/nginx/dhparam.pem".parse().unwrap()));
+    assert!(parse_directives("ssl_dhparam;").is_err());
+}
+
+#[test]
+fn ecdh_curve() {
+    assert_eq!(item("ssl_ecdh_curve X25519:prime256v1;"),
+        Item::SslEcdhCurve("X25519:prime256v1".parse().unwrap()));
+    assert_eq!(item("ssl_ecdh_curve auto;"),
+        Item::SslEcdhCurve("auto".parse().unwrap()));
+}
+
+#[test]
+fn prefer_server_ciphers() {
+    assert_eq!(item("ssl_prefer_server_ciphers on;"),
+        Item::SslPreferServerCiphers(true));
+    assert_eq!(item("ssl_prefer_server_ciphers off;"),
+        Item::SslPreferServerCiphers(false));
+    assert!(parse_directives("ssl_prefer_server_ciphers yes;").is_err());
+}
+
+#[test]
+fn server_ssl_directives() {
+    let ast = parse_main_with(r#"
+        server {
+            listen 443 ssl;
+            ssl_certificate /keys/my.crt;
+            ssl_certificate_key /keys/my.key;
+            ssl_protocols TLSv1.2 TLSv1.3;
+            ssl_prefer_server_ciphers off;
+            root /var/www;
+            location / {
+                ssl_dhparam /keys/dh.pem;
+            }
+        }
+    "#, &ParseOptions::default()).unwrap();
+    let server = match ast.directives[0].item {
+        Item::Server(ref s) => s,
+        ref item => panic!("not a server: {:?}", item),
+    };
+    let names = server.ssl_directives().iter()
+        .map(|d| d.item.keyword())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![
+        "ssl_certificate",
+        "ssl_certificate_key",
+        "ssl_protocols",
+        "ssl_prefer_server_ciphers",
+    ]);
+}
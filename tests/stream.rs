@@ -0,0 +1,44 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+use nginx_config::ast::{Item, Address};
+
+
+#[test]
+fn minimal() {
+    let ast = parse_main("
+        stream {
+            upstream backend {
+                server backend1.example.com:12345;
+            }
+            server {
+                listen 12345 udp;
+                proxy_pass backend;
+            }
+        }
+    ").unwrap();
+    let stream = match ast.directives[0].item {
+        Item::Stream(ref s) => s,
+        ref item => panic!("not a stream: {:?}", item),
+    };
+    let names = stream.directives.iter()
+        .map(|d| d.item.directive_name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["upstream", "server"]);
+    let listens = ast.listens();
+    assert_eq!(listens.len(), 1);
+    assert_eq!(listens[0].address, Address::Port(12345));
+    assert!(listens[0].udp);
+    assert!(ast.has_directive("proxy_pass"));
+}
+
+#[test]
+fn not_http_server() {
+    let ast = parse_main("
+        stream { server { listen 80; } }
+        http { server { listen 80; server_name web; } }
+    ").unwrap();
+    let server = ast.server_for_host("any", 80).unwrap();
+    assert_eq!(server.directives.len(), 2);
+}
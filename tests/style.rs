@@ -0,0 +1,156 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::fmt;
+use std::io;
+
+use nginx_config::{parse_main, parse_main_with, ParseOptions, Style};
+
+
+fn sorted(text: &str) -> String {
+    // comments are only kept by the lenient parser
+    let ast = parse_main_with(text, &ParseOptions::default()).unwrap();
+    ast.to_string_with(Style::default().sort_directives(true))
+}
+
+#[test]
+fn default_keeps_order() {
+    let ast = parse_main("root /www;\ngzip on;\n").unwrap();
+    assert_eq!(ast.to_string_with(&Style::default()), ast.to_string());
+    assert_eq!(ast.to_string(), "root /www;\ngzip on;\n");
+}
+
+#[test]
+fn indent() {
+    let ast = parse_main("server { root /www; }").unwrap();
+    assert_eq!(ast.to_string_with(Style::default().indent(2)),
+        "server {\n  root /www;\n}\n");
+}
+
+#[test]
+fn sort_with_comments() {
+    assert_eq!(sorted("\
+        server {
+            # static files
+            root /www;
+            # compression
+            gzip on;
+            location /b { root /b; }
+            location /a { root /a; }
+            index index.html;
+        }
+    "), "\
+server {
+    # compression
+    gzip on;
+    index index.html;
+
+    location /b {
+        root /b;
+    }
+
+    location /a {
+        root /a;
+    }
+    # static files
+    root /www;
+}
+");
+}
+
+#[test]
+fn sort_keeps_rewrites() {
+    assert_eq!(sorted("\
+        root /www;
+        gzip on;
+        rewrite ^/old$ /new;
+        return 404;
+        deny all;
+        allow 127.0.0.1;
+        index index.html;
+        expires 1h;
+    "), "\
+gzip on;
+root /www;
+rewrite ^/old$ /new;
+return 404;
+deny all;
+allow 127.0.0.1;
+expires 1h;
+index index.html;
+");
+}
+
+/// Collects pieces as written, fails after `limit` pieces
+struct Sink {
+    pieces: Vec<String>,
+    limit: usize,
+}
+
+impl fmt::Write for Sink {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.pieces.len() >= self.limit {
+            return Err(fmt::Error);
+        }
+        self.pieces.push(s.to_string());
+        Ok(())
+    }
+}
+
+#[test]
+fn write_to_sink() {
+    let ast = parse_main("http { server { listen 80; root /www; } }").unwrap();
+    let mut sink = Sink { pieces: Vec::new(), limit: usize::MAX };
+    ast.write_to(&mut sink, &Style::default()).unwrap();
+    assert!(sink.pieces.len() > 1);
+    assert_eq!(sink.pieces.concat(), ast.to_string());
+
+    let mut sink = Sink { pieces: Vec::new(), limit: 3 };
+    assert_eq!(ast.write_to(&mut sink, &Style::default()), Err(fmt::Error));
+    assert_eq!(sink.pieces.len(), 3);
+}
+
+#[test]
+fn to_writer() {
+    let ast = parse_main("http { server { listen 80; root /www; } }").unwrap();
+    let mut buf = Vec::new();
+    ast.to_writer(&mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), ast.to_string());
+
+    let mut small = [0u8; 10];
+    let err = ast.to_writer(&mut small[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+}
+
+#[test]
+fn minified() {
+    let text = r#"
+        # main config
+        http {
+            server {
+                listen 80;  # plain http
+                server_name example.com;
+                add_header X-Note "a {b} c ";
+
+                location ~ \.php$ {
+                    fastcgi_pass unix:/run/php.sock;
+                }
+            }
+        }
+    "#;
+    let ast = parse_main_with(text, &ParseOptions::default()).unwrap();
+    let min = ast.render_minified();
+    assert_eq!(min, "http{server{listen 80;server_name example.com;\
+        add_header X-Note \"a {b} c \";\
+        location ~ \\.php$ {fastcgi_pass unix:/run/php.sock;}}}");
+    assert!(min.len() < ast.to_string().len());
+    assert_eq!(parse_main(&min).unwrap(), parse_main(text).unwrap());
+}
+
+#[test]
+fn minify_ignores_indent() {
+    let ast = parse_main("server { listen 80; }").unwrap();
+    let mut style = Style::default();
+    style.indent(2).minify(true);
+    assert_eq!(ast.to_string_with(&style), ast.render_minified());
+}
@@ -0,0 +1,36 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::time::Duration;
+
+use nginx_config::parse_main;
+use nginx_config::ast::Item;
+
+
+fn item(text: &str) -> Item {
+    parse_main(text).unwrap().directives.remove(0).item
+}
+
+#[test]
+fn client_timeouts() {
+    assert_eq!(item("client_body_timeout 1m30s;"),
+               Item::ClientBodyTimeout(Duration::from_secs(90)));
+    assert_eq!(item("client_header_timeout 15;"),
+               Item::ClientHeaderTimeout(Duration::from_secs(15)));
+    assert_eq!(item("send_timeout 1h2m3s;"),
+               Item::SendTimeout(Duration::from_secs(3723)));
+}
+
+#[test]
+fn formatted() {
+    assert_eq!(item("send_timeout 90s;").to_string(),
+               "send_timeout 1m30s;\n");
+    assert_eq!(item("client_body_timeout 1500ms;").to_string(),
+               "client_body_timeout 1s500ms;\n");
+}
+
+#[test]
+fn invalid() {
+    assert!(parse_main("send_timeout 1x;").is_err());
+    assert!(parse_main("client_body_timeout 30s 30s;").is_err());
+}
@@ -0,0 +1,70 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::Pos;
+use nginx_config::tokenizer::{tokens, token_kinds, Kind};
+
+
+fn pos(line: usize, column: usize) -> Pos {
+    Pos { line, column }
+}
+
+#[test]
+fn listen() {
+    let toks = tokens("listen 80;")
+        .map(|t| t.map(|(p, t)| (p, t.kind, t.value)))
+        .collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(toks, vec![
+        (pos(1, 1), Kind::String, "listen"),
+        (pos(1, 8), Kind::String, "80"),
+        (pos(1, 10), Kind::Semicolon, ";"),
+    ]);
+}
+
+#[test]
+fn error_stops() {
+    let mut iter = tokens("root '/var/www;\n");
+    assert!(iter.next().unwrap().is_ok());
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn comment() {
+    let toks = tokens("# x\nlisten 80; # main\n")
+        .map(|t| t.map(|(p, t)| (p, t.kind, t.value)))
+        .collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(toks, vec![
+        (pos(1, 1), Kind::Comment, "# x"),
+        (pos(2, 1), Kind::String, "listen"),
+        (pos(2, 8), Kind::String, "80"),
+        (pos(2, 10), Kind::Semicolon, ";"),
+        (pos(2, 12), Kind::Comment, "# main"),
+    ]);
+}
+
+#[test]
+fn kinds() {
+    use nginx_config::tokenizer::TokenKind::*;
+    let kinds = token_kinds(r#"
+        # comment
+        server {
+            root $root/html;
+            add_header X-Test "a b";
+        }
+    "#).map(|t| t.map(|(_, k, v)| (k, v)))
+        .collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(kinds, vec![
+        (Comment, "# comment"),
+        (Ident, "server"),
+        (OpenBrace, "{"),
+        (Ident, "root"),
+        (Variable, "$root/html"),
+        (Semi, ";"),
+        (Ident, "add_header"),
+        (Value, "X-Test"),
+        (String, "\"a b\""),
+        (Semi, ";"),
+        (CloseBrace, "}"),
+    ]);
+}
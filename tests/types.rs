@@ -0,0 +1,41 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+use nginx_config::ast::{Item, TypeEntry};
+
+
+fn types(text: &str) -> Vec<TypeEntry> {
+    match parse_main(text).unwrap().directives.remove(0).item {
+        Item::Types(entries) => entries,
+        item => panic!("not a types block: {:?}", item),
+    }
+}
+
+#[test]
+fn entries() {
+    assert_eq!(types("types {
+        text/html html htm;
+        image/png png;
+    }"), vec![
+        TypeEntry {
+            mime: "text/html".into(),
+            extensions: vec!["html".into(), "htm".into()],
+        },
+        TypeEntry {
+            mime: "image/png".into(),
+            extensions: vec!["png".into()],
+        },
+    ]);
+}
+
+#[test]
+fn empty() {
+    assert_eq!(types("types {}"), vec![]);
+}
+
+#[test]
+fn invalid() {
+    assert!(parse_main("types { text/html; }").is_err());
+    assert!(parse_main("types text/html html;").is_err());
+}
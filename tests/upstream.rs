@@ -0,0 +1,146 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::time::Duration;
+
+use nginx_config::{parse_main, parse_main_with, ParseOptions};
+use nginx_config::ast::{BalancingMethod, Item, Upstream};
+
+
+fn upstream(text: &str) -> Upstream {
+    match parse_main(text).unwrap().directives.remove(0).item {
+        Item::Upstream(u) => u,
+        item => panic!("not an upstream: {:?}", item),
+    }
+}
+
+fn balancing(text: &str) -> Option<BalancingMethod> {
+    upstream(&format!("upstream app {{ {} server 10.0.0.1; }}", text))
+        .balancing
+}
+
+#[test]
+fn round_robin() {
+    assert_eq!(balancing(""), None);
+}
+
+#[test]
+fn least_conn() {
+    assert_eq!(balancing("least_conn;"), Some(BalancingMethod::LeastConn));
+}
+
+#[test]
+fn ip_hash() {
+    assert_eq!(balancing("ip_hash;"), Some(BalancingMethod::IpHash));
+}
+
+#[test]
+fn hash() {
+    assert_eq!(balancing("hash $remote_addr;"),
+        Some(BalancingMethod::Hash {
+            key: "$remote_addr".parse().unwrap(),
+            consistent: false,
+        }));
+    assert_eq!(balancing("hash $request_uri consistent;"),
+        Some(BalancingMethod::Hash {
+            key: "$request_uri".parse().unwrap(),
+            consistent: true,
+        }));
+}
+
+#[test]
+fn servers() {
+    let u = upstream("upstream app {
+        server 10.0.0.1:8080 weight=2;
+        # comment
+        server unix:/tmp/app.sock;
+    }");
+    assert_eq!(u.name, "app");
+    assert_eq!(u.servers.len(), 2);
+    assert_eq!(u.servers[0].address.to_string(), "10.0.0.1:8080");
+    assert_eq!(u.servers[0].weight, Some(2));
+    assert_eq!(u.servers[0].params, vec![]);
+    assert_eq!(u.servers[1].address.to_string(), "unix:/tmp/app.sock");
+}
+
+#[test]
+fn keepalive() {
+    let u = upstream("upstream app {
+        server 10.0.0.1:8080;
+        keepalive 16;
+        keepalive_requests 100;
+        keepalive_timeout 60s;
+    }");
+    assert_eq!(u.keepalive, Some(16));
+    assert_eq!(u.keepalive_requests, Some(100));
+    assert_eq!(u.keepalive_timeout, Some(Duration::from_secs(60)));
+}
+
+#[test]
+fn keepalive_unset() {
+    let u = upstream("upstream app { server 10.0.0.1:8080; }");
+    assert_eq!(u.keepalive, None);
+    assert_eq!(u.keepalive_requests, None);
+    assert_eq!(u.keepalive_timeout, None);
+}
+
+#[test]
+fn keepalive_invalid() {
+    assert!(parse_main("upstream app { keepalive many; }").is_err());
+    assert!(parse_main("upstream app { keepalive_timeout 5x; }").is_err());
+}
+
+#[test]
+fn total_weight() {
+    let u = upstream("upstream app {
+        server 10.0.0.1 weight=5;
+        server 10.0.0.2;
+        server 10.0.0.3 max_fails=3 weight=2;
+    }");
+    assert_eq!(u.servers[1].weight, None);
+    assert_eq!(u.servers[2].params, vec!["max_fails=3".parse().unwrap()]);
+    assert_eq!(u.total_weight(), 8);
+}
+
+#[test]
+fn total_weight_skips_backup_and_down() {
+    let u = upstream("upstream app {
+        server 10.0.0.1 weight=5;
+        server 10.0.0.2 weight=3 backup;
+        server 10.0.0.3 down;
+        server 10.0.0.4;
+    }");
+    assert_eq!(u.total_weight(), 6);
+}
+
+#[test]
+fn total_weight_saturates() {
+    let u = upstream("upstream app {
+        server 10.0.0.1 weight=4294967295;
+        server 10.0.0.2 weight=10;
+    }");
+    assert_eq!(u.total_weight(), u32::MAX);
+}
+
+#[test]
+fn unknown_directives_and_comments() {
+    let text = "upstream u {\n\
+        zone z 64k;\n\
+        # primary\n\
+        server a;\n\
+        random; # two choices\n\
+    }";
+    assert!(parse_main(text).is_err());
+    let ast = parse_main_with(text, &ParseOptions::default()).unwrap();
+    let u = match ast.directives[0].item {
+        Item::Upstream(ref u) => u,
+        ref item => panic!("not an upstream: {:?}", item),
+    };
+    assert_eq!(u.servers.len(), 1);
+    let names = u.directives.iter().map(|d| d.item.keyword())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["zone", "#", "random"]);
+    assert_eq!(u.directives[2].trailing_comment.as_ref().unwrap(),
+        " two choices");
+    assert!(ast.all_directives().any(|d| d.item.keyword() == "zone"));
+}
@@ -0,0 +1,300 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::{parse_main, parse_main_with, ParseOptions, Pos};
+use nginx_config::ast::Item;
+
+
+#[test]
+fn undefined_named_locations() {
+    let ast = parse_main(r#"
+        server {
+            location / {
+                try_files $uri @missing;
+                error_page 404 @app;
+            }
+            location @app {
+                proxy_pass http://app;
+            }
+        }
+        server {
+            location / {
+                try_files $uri @app;
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.undefined_named_locations(), vec!["missing", "app"]);
+}
+
+#[test]
+fn root_alias_conflicts() {
+    let ast = parse_main(r#"
+        server {
+            location /a {
+                root /var/www;
+                alias /var/www/a;
+            }
+            location /b {
+                root /var/www;
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.root_alias_conflicts(), vec![Pos { line: 3, column: 13 }]);
+}
+
+#[test]
+fn ssl_without_cert() {
+    let ast = parse_main(r#"
+        http {
+            server {
+                listen 443 ssl;
+            }
+            server {
+                listen 443 ssl;
+                ssl_certificate /etc/ssl/cert.pem;
+            }
+            server {
+                listen 80;
+            }
+        }
+        http {
+            ssl_certificate /etc/ssl/cert.pem;
+            server {
+                listen 443 ssl;
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.ssl_without_cert(), vec![Pos { line: 3, column: 13 }]);
+}
+
+#[test]
+fn unknown_variables() {
+    let ast = parse_main(r#"
+        http {
+            server {
+                set $backend app;
+                location / {
+                    proxy_pass http://$backend;
+                    proxy_set_header Host $hostt;
+                    proxy_set_header X-Real-Ip $remote_addr;
+                    add_header X-Agent $http_user_agent;
+                    rewrite ^/(.*)$ /$1 break;
+                    return 301 https://$hostt$request_uri;
+                }
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.unknown_variables(), vec!["hostt"]);
+}
+
+#[test]
+fn geoip_variables() {
+    let ast = parse_main(r#"
+        http {
+            geoip_country /usr/share/GeoIP/GeoIP.dat;
+            geoip_city /usr/share/GeoIP/GeoLiteCity.dat;
+            geoip_org /usr/share/GeoIP/GeoIPASNum.dat;
+            server {
+                add_header X-Country $geoip_country_code;
+                add_header X-City "$geoip_city, $geoip_region_name";
+                add_header X-Org $geoip_org;
+                add_header X-Geo $geoip_latitude,$geoip_longitude;
+                add_header X-Typo $geoip_countr_code;
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.unknown_variables(), vec!["geoip_countr_code"]);
+}
+
+#[test]
+fn risky_if_usage() {
+    let ast = parse_main(r#"
+        server {
+            if ($host = old.example.com) {
+                proxy_pass http://old;
+            }
+            location / {
+                if ($request_method = POST) {
+                    proxy_pass http://app;
+                }
+                if ($http_user_agent ~ bot) {
+                    return 403;
+                }
+                if ($uri ~ ^/old/) {
+                    rewrite ^/old/(.*)$ /new/$1 last;
+                }
+                if ($args) {
+                    rewrite ^ /search break;
+                }
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.risky_if_usage(), vec![
+        Pos { line: 7, column: 17 },
+        Pos { line: 16, column: 17 },
+    ]);
+}
+
+#[test]
+fn invalid_default_types() {
+    let ast = parse_main(r#"
+        default_type application/octet-stream;
+        http {
+            default_type "text/plain; charset=utf-8";
+            server {
+                default_type text;
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.directives[0].item,
+        Item::DefaultType("application/octet-stream".parse().unwrap()));
+    assert_eq!(ast.invalid_default_types(), vec![Pos { line: 6, column: 17 }]);
+}
+
+#[test]
+fn proxy_pass_slash_mismatches() {
+    let ast = parse_main(r#"
+        server {
+            location /api/ {
+                proxy_pass http://app/v1;
+            }
+            location ^~ /static {
+                proxy_pass http://cdn/;
+            }
+            location /ok/ {
+                proxy_pass http://app/v2/;
+            }
+            location /plain {
+                proxy_pass http://app;
+            }
+            location /dynamic/ {
+                proxy_pass http://app/$arg_v;
+            }
+            location ~ ^/re/ {
+                proxy_pass http://app/x;
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.proxy_pass_slash_mismatches(), vec![
+        Pos { line: 4, column: 17 },
+        Pos { line: 7, column: 17 },
+    ]);
+}
+
+#[test]
+fn exposes_server_tokens() {
+    let ast = parse_main(r#"
+        http {
+            server_tokens on;
+            server {
+                server_tokens off;
+            }
+            server {
+                server_tokens "custom";
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.exposes_server_tokens(), vec![Pos { line: 3, column: 13 }]);
+}
+
+#[test]
+fn merge_slashes_disabled() {
+    let ast = parse_main(r#"
+        merge_slashes on;
+        http {
+            server {
+                merge_slashes off;
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.directives[0].item, Item::MergeSlashes(true));
+    assert_eq!(ast.merge_slashes_disabled(), vec![Pos { line: 5, column: 17 }]);
+    assert!(parse_main("merge_slashes yes;").is_err());
+}
+
+#[test]
+fn rewrite_last_in_if() {
+    let ast = parse_main(r#"
+        server {
+            if ($host = old.example.com) {
+                rewrite ^ /legacy$uri last;
+            }
+            location /app/ {
+                if ($arg_v = 1) {
+                    rewrite ^/app/(.*)$ /app/v1/$1 last;
+                }
+                if ($arg_v = 2) {
+                    rewrite ^/app/(.*)$ /v2/$1 break;
+                }
+                if ($arg_v = 3) {
+                    rewrite ^/app/(.*)$ /v3/$1 last;
+                }
+                rewrite ^/app/old$ /app/new last;
+            }
+            location = /old {
+                if ($arg_x) {
+                    rewrite ^ /old last;
+                }
+            }
+            location ~ ^/re/ {
+                if ($arg_x) {
+                    rewrite ^/re/(.*)$ /re/x/$1 last;
+                }
+            }
+        }
+    "#).unwrap();
+    assert_eq!(ast.rewrite_last_in_if(), vec![
+        Pos { line: 8, column: 21 },
+        Pos { line: 14, column: 21 },
+        Pos { line: 20, column: 21 },
+        Pos { line: 25, column: 21 },
+    ]);
+    // `rewrite ... last` is left to the lint above, unlike `break`
+    assert_eq!(ast.risky_if_usage(), vec![Pos { line: 10, column: 17 }]);
+}
+
+#[test]
+fn dynamic_proxy_pass_without_resolver() {
+    let ast = parse_main_with(r#"
+        http {
+            upstream backend {
+                server 127.0.0.1:8080;
+            }
+            server {
+                location / {
+                    proxy_pass http://$arg_host;
+                }
+            }
+            server {
+                resolver 127.0.0.53;
+                location / {
+                    proxy_pass http://$arg_host;
+                }
+            }
+            server {
+                location / {
+                    resolver 127.0.0.53;
+                    proxy_pass https://api.example.com$request_uri;
+                }
+                location /app {
+                    proxy_pass http://backend$request_uri;
+                }
+                location /local {
+                    proxy_pass http://127.0.0.1:8000$request_uri;
+                }
+                location /static {
+                    proxy_pass http://cdn.example.com;
+                }
+            }
+            server {
+                location / {
+                    proxy_pass https://api.example.com$request_uri;
+                }
+            }
+        }
+    "#, &ParseOptions::default()).unwrap();
+    assert_eq!(ast.dynamic_proxy_pass_without_resolver(), vec![
+        Pos { line: 6, column: 13 },
+        Pos { line: 32, column: 13 },
+    ]);
+}
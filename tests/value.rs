@@ -0,0 +1,134 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::ast::Value;
+
+
+#[test]
+fn push_pieces() {
+    let mut val: Value = "/".parse().unwrap();
+    val.push_variable("uri");
+    val.push_literal("?");
+    val.push_variable("args");
+    assert_eq!(val.to_string(), "/$uri?$args");
+}
+
+#[test]
+fn push_literal_merges() {
+    let mut val: Value = "/a".parse().unwrap();
+    val.push_literal("/b");
+    val.push_literal("/c");
+    assert_eq!(val, "/a/b/c".parse().unwrap());
+}
+
+#[test]
+fn absolute_path() {
+    let val = |s: &str| s.parse::<Value>().unwrap();
+    assert!(val("/var/www").is_absolute_path());
+    assert!(val("/var/$host").is_absolute_path());
+    assert!(!val("html").is_absolute_path());
+    assert!(!val("$doc_root/html").is_absolute_path());
+}
+
+#[test]
+fn join_quoted() {
+    let val = |s: &str| s.parse::<Value>().unwrap();
+    assert_eq!(Value::join_quoted(vec![val("'a'"), val("'b'")]),
+               vec![val("ab")]);
+    assert_eq!(Value::join_quoted(vec![
+        val("x"), val("'$a '"), val("\"$b\""), val("y"), val("'c'"),
+    ]), vec![val("x"), val("'$a $b'"), val("y"), val("c")]);
+}
+
+#[test]
+fn raw_string() {
+    let val: Value = "'$host is up'".parse().unwrap();
+    assert_eq!(val.to_string(), "\"$host is up\"");
+    assert_eq!(val.to_raw_string(), "$host is up");
+    let val: Value = "/index.html".parse().unwrap();
+    assert_eq!(val.to_raw_string(), val.to_string());
+}
+
+#[test]
+fn dynamic_variable_family() {
+    assert_eq!(Value::dynamic_variable_family("arg_foo"), Some("arg"));
+    assert_eq!(Value::dynamic_variable_family("http_user_agent"),
+               Some("http"));
+    assert_eq!(Value::dynamic_variable_family("request_uri"), None);
+    assert_eq!(Value::dynamic_variable_family("arg_"), None);
+    let val: Value = "$arg_foo/$http_user_agent".parse().unwrap();
+    let families = val.variables()
+        .filter_map(Value::dynamic_variable_family)
+        .collect::<Vec<_>>();
+    assert_eq!(families, vec!["arg", "http"]);
+}
+
+#[test]
+fn variable_boundary() {
+    let mut val: Value = "$host".parse().unwrap();
+    val.push_literal("name");
+    assert_eq!(val.to_string(), "${host}name");
+    let mut val: Value = "$host".parse().unwrap();
+    val.push_literal("_1");
+    assert_eq!(val.to_string(), "${host}_1");
+    let mut val: Value = "$host".parse().unwrap();
+    val.push_literal(".local");
+    assert_eq!(val.to_string(), "$host.local");
+    let val: Value = "${uri}_$args".parse().unwrap();
+    assert_eq!(val.to_string(), "${uri}_$args");
+}
+
+#[test]
+fn variable_before_brace() {
+    let mut val: Value = "$host".parse().unwrap();
+    val.push_literal("{x}");
+    assert_eq!(val.to_string(), "\"${host}{x}\"");
+    assert_eq!(val.to_string().parse::<Value>().unwrap(), val);
+    let val: Value = "'${uri}{'".parse().unwrap();
+    assert_eq!(val.to_raw_string(), "${uri}{");
+}
+
+#[test]
+fn join() {
+    let val = |s: &str| s.parse::<Value>().unwrap();
+    let url = val("$scheme://").join(val("$host")).join(val("$uri"));
+    assert_eq!(url.to_string(), "$scheme://$host$uri");
+    assert_eq!(url, val("$scheme://$host$uri"));
+    let joined = val("/static").join(val("/img/$file"));
+    assert_eq!(joined, val("/static/img/$file"));
+    assert_eq!(val("$host").join(val("name")).to_string(), "${host}name");
+    assert_eq!(val("'a '").join(val("b")).to_string(), "\"a b\"");
+}
+
+#[test]
+fn needs_quoting() {
+    let val = |s: &str| s.parse::<Value>().unwrap();
+    assert!(!val("/var/www").needs_quoting());
+    assert!(!val("$scheme://$host").needs_quoting());
+    assert!(!val("'quoted-but-plain'").needs_quoting());
+    assert!(val("'two words'").needs_quoting());
+    assert!(val("\"a;b\"").needs_quoting());
+    assert!(val("'{}'").needs_quoting());
+    assert!(val("\"\"").needs_quoting());
+    assert!(val("\"#fff\"").needs_quoting());
+    assert!(!val("a#b").needs_quoting());
+}
+
+#[test]
+fn needs_quoting_quotes() {
+    let val = |s: &str| s.parse::<Value>().unwrap();
+    assert!(val("\"it's\"").needs_quoting());
+    assert!(val("'say \"hi\"'").needs_quoting());
+    assert!(val(r#""a\"b""#).needs_quoting());
+    assert!(val(r"a\.b").needs_quoting());
+    assert_eq!(val("\"it's\"").to_string(), "\"it's\"");
+    assert_eq!(val(r"a\.b").to_string(), r#""a\.b""#);
+}
+
+#[test]
+fn unclosed_variable_brace() {
+    let err = |s: &str| s.parse::<Value>().unwrap_err();
+    assert!(err("a${b").contains("unclosed variable brace"));
+    assert!(err("\"a${b\"").contains("unclosed variable brace"));
+    assert!(err("\"a${b").contains("unclosed variable brace"));
+}
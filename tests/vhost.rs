@@ -0,0 +1,123 @@
+extern crate nginx_config;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use nginx_config::parse_main;
+use nginx_config::ast::{Main, Item};
+
+
+const CONFIG: &str = r#"
+    http {
+        server {
+            listen 80;
+            server_name first;
+            root /first;
+        }
+        server {
+            listen 80;
+            server_name example.org www.example.org;
+            root /exact;
+        }
+        server {
+            listen 80;
+            server_name *.example.org;
+            root /star;
+        }
+        server {
+            listen 80;
+            server_name *.api.example.org;
+            root /longer-star;
+        }
+        server {
+            listen 80;
+            listen 443 ssl default_server;
+            server_name mail.*;
+            root /mail;
+        }
+        server {
+            listen 80 default_server;
+            server_name ~^(?<user>.+)\.users\.example\.net$;
+            root /regex;
+        }
+        server {
+            listen 8080;
+            server_name .example.com;
+            root /dot;
+        }
+    }
+"#;
+
+fn root(ast: &Main, host: &str, port: u16) -> Option<String> {
+    let server = ast.server_for_host(host, port)?;
+    server.directives.iter().filter_map(|d| match d.item {
+        Item::Root(ref val) => Some(val.to_string()),
+        _ => None,
+    }).next()
+}
+
+#[test]
+fn exact() {
+    let ast = parse_main(CONFIG).unwrap();
+    assert_eq!(root(&ast, "example.org", 80).unwrap(), "/exact");
+    assert_eq!(root(&ast, "WWW.Example.org.", 80).unwrap(), "/exact");
+    assert_eq!(root(&ast, "first", 80).unwrap(), "/first");
+}
+
+#[test]
+fn wildcard() {
+    let ast = parse_main(CONFIG).unwrap();
+    assert_eq!(root(&ast, "img.example.org", 80).unwrap(), "/star");
+    assert_eq!(root(&ast, "v1.api.example.org", 80).unwrap(),
+               "/longer-star");
+    assert_eq!(root(&ast, "mail.example.net", 80).unwrap(), "/mail");
+    assert_eq!(root(&ast, "example.com", 8080).unwrap(), "/dot");
+    assert_eq!(root(&ast, "www.example.com", 8080).unwrap(), "/dot");
+}
+
+#[test]
+fn default_fallback() {
+    let ast = parse_main(CONFIG).unwrap();
+    assert_eq!(root(&ast, "unknown.net", 80).unwrap(), "/regex");
+    assert_eq!(root(&ast, "example.org", 443).unwrap(), "/mail");
+    assert_eq!(root(&ast, "notexample.com", 8080).unwrap(), "/dot");
+    assert!(ast.server_for_host("example.org", 8443).is_none());
+}
+
+#[test]
+fn first_server_is_default() {
+    let ast = parse_main("
+        server { server_name a; root /a; }
+        server { server_name b; root /b; }
+    ").unwrap();
+    assert_eq!(root(&ast, "b", 80).unwrap(), "/b");
+    assert_eq!(root(&ast, "c", 80).unwrap(), "/a");
+    assert!(ast.server_for_host("a", 8000).is_none());
+}
+
+#[cfg(feature="regex")]
+#[test]
+fn regex() {
+    let ast = parse_main(r#"
+        server { server_name default; root /default; }
+        server { server_name ~^www\d+\.; root /numbered; }
+        server { server_name ~^www; root /www; }
+        server { server_name www.*; root /tail; }
+    "#).unwrap();
+    assert_eq!(root(&ast, "WWW2.example.org", 80).unwrap(), "/numbered");
+    assert_eq!(root(&ast, "wwwx", 80).unwrap(), "/www");
+    assert_eq!(root(&ast, "www.example.org", 80).unwrap(), "/tail");
+    assert_eq!(root(&ast, "example.org", 80).unwrap(), "/default");
+}
+
+#[test]
+fn dot_suffix_matches_bare_name() {
+    let ast = parse_main("
+        server { listen 80 default_server; server_name other; root /other; }
+        server { listen 80; server_name .example.com; root /dot; }
+    ").unwrap();
+    assert_eq!(root(&ast, "example.com", 80).unwrap(), "/dot");
+    assert_eq!(root(&ast, "Example.COM.", 80).unwrap(), "/dot");
+    assert_eq!(root(&ast, "www.example.com", 80).unwrap(), "/dot");
+    assert_eq!(root(&ast, "a.b.example.com", 80).unwrap(), "/dot");
+    assert_eq!(root(&ast, "notexample.com", 80).unwrap(), "/other");
+    assert_eq!(root(&ast, "example.com.org", 80).unwrap(), "/other");
+}